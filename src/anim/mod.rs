@@ -1,6 +1,7 @@
 use graphics::math::Matrix2d;
 use graphics::*;
 
+#[derive(Debug, Copy, Clone)]
 pub enum AnimationType {
     Explosion,
 }
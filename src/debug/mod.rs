@@ -0,0 +1,172 @@
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+use graphics::*;
+
+use crate::qt::*;
+use crate::world::map::Map;
+use crate::world::Actor;
+
+/// How long, in seconds, a query's region stays on screen after it's made.
+const QUERY_LINGER: f64 = 0.3;
+
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// Show what the selected actor can see. Works separately from the rest
+    /// of the overlay.
+    pub sight: bool,
+    leaves: Vec<(RectangleData, usize)>,
+    queries: Vec<(Region, f64)>,
+}
+
+impl DebugOverlay {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.leaves.clear();
+        self.queries.clear();
+    }
+
+    pub fn toggle_sight(&mut self) {
+        self.sight = !self.sight;
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        for (_, age) in self.queries.iter_mut() {
+            *age += dt;
+        }
+        self.queries.retain(|(_, age)| *age < QUERY_LINGER);
+    }
+
+    /// Take a snapshot of the tree's layout and pick up any queries that were
+    /// made against it.
+    pub fn observe<T: HasRegion + Clone>(&mut self, qt: &QuadTree<T>) {
+        self.leaves = qt.leaves();
+        self.record_queries(qt);
+    }
+
+    pub fn record_queries<T: HasRegion + Clone>(&mut self, qt: &QuadTree<T>) {
+        for region in qt.take_queries() {
+            self.queries.push((region, 0.0));
+        }
+    }
+
+    pub fn render<G: Graphics>(&self, t: Matrix2d, g: &mut G) {
+        if !self.enabled {
+            return;
+        }
+        for (bounds, count) in self.leaves.iter() {
+            // Green when empty, shading to red as the node fills up to the
+            // point where it would split.
+            let fullness = (*count as f32 / SPLIT_SIZE as f32).min(1.0);
+            let rect = [bounds.x(), bounds.y(), bounds.w(), bounds.h()];
+            if *count > 0 {
+                rectangle([fullness, 1.0 - fullness, 0.2, 0.15], rect, t, g);
+            }
+            Rectangle::new_border([fullness, 1.0 - fullness, 0.2, 0.6], 0.5).draw(
+                rect,
+                &Default::default(),
+                t,
+                g,
+            );
+        }
+        for (region, age) in self.queries.iter() {
+            let alpha = 0.4 * (1.0 - (age / QUERY_LINGER) as f32);
+            render_region(region, [1.0, 0.9, 0.2, alpha], t, g);
+        }
+    }
+
+    /// Light up every cell actor `i` has a clear view of, and draw a line to
+    /// each actor it can see.
+    pub fn render_sight<G: Graphics>(
+        &self,
+        i: usize,
+        actors: &[Actor],
+        map: &Map,
+        t: Matrix2d,
+        g: &mut G,
+    ) {
+        if !self.sight {
+            return;
+        }
+        let actor = &actors[i];
+        let (x, y) = actor.get_pos();
+        let region = match actor.get_sight_range() {
+            Some(region) => region.bounding_box(),
+            None => return,
+        };
+        // The sight range can hang off the edge of the map, so clamp it back
+        // on.
+        let bounds = map.get_bounds();
+        let (x1, y1) = bounds.constrain(region.x(), region.y());
+        let (x2, y2) = bounds.constrain(region.x() + region.w(), region.y() + region.h());
+        let ((min_x, min_y), (max_x, max_y)) = match (map.cell_at(x1, y1), map.cell_at(x2, y2)) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return,
+        };
+        for cell_x in min_x..=max_x {
+            for cell_y in min_y..=max_y {
+                let (cx, cy) = map.cell_center(cell_x, cell_y);
+                if actor.can_see(cx, cy) && map.line_of_sight(x, y, cx, cy) {
+                    map.render_cell(cell_x, cell_y, [1.0, 1.0, 0.4, 0.25], t, g);
+                }
+            }
+        }
+        for other in actor.perception.visible() {
+            let (ox, oy) = actors[other].get_pos();
+            line([0.4, 1.0, 0.4, 0.8], 0.5, [x, y, ox, oy], t, g);
+        }
+    }
+}
+
+/// Draw the given region filled in with a single color.
+pub fn render_region<G: Graphics>(region: &Region, color: Color, t: Matrix2d, g: &mut G) {
+    match region {
+        Region::Point(data) => {
+            ellipse(
+                color,
+                rectangle::centered_square(data.x(), data.y(), 1.5),
+                t,
+                g,
+            );
+        }
+        Region::Rectangle(data) => {
+            rectangle(color, [data.x(), data.y(), data.w(), data.h()], t, g);
+        }
+        Region::Circle(data) => {
+            ellipse(
+                color,
+                rectangle::centered_square(data.x(), data.y(), data.r()),
+                t,
+                g,
+            );
+        }
+        Region::Polygon(data) => {
+            let points: Vec<[f64; 2]> = data.points().iter().map(|p| [p.x(), p.y()]).collect();
+            polygon(color, &points, t, g);
+        }
+        Region::Capsule(data) => {
+            let ((x1, y1), (x2, y2), r) = (data.start(), data.end(), data.r());
+            ellipse(color, rectangle::centered_square(x1, y1, r), t, g);
+            ellipse(color, rectangle::centered_square(x2, y2, r), t, g);
+            let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+            if length > 0.0 {
+                let (nx, ny) = (-(y2 - y1) / length * r, (x2 - x1) / length * r);
+                polygon(
+                    color,
+                    &[
+                        [x1 + nx, y1 + ny],
+                        [x2 + nx, y2 + ny],
+                        [x2 - nx, y2 - ny],
+                        [x1 - nx, y1 - ny],
+                    ],
+                    t,
+                    g,
+                );
+            }
+        }
+        Region::OrientedRectangle(data) => {
+            let corners: Vec<[f64; 2]> = data.corners().iter().map(|&(x, y)| [x, y]).collect();
+            polygon(color, &corners, t, g);
+        }
+    }
+}
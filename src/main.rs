@@ -21,6 +21,7 @@ use rand::Rng;
 
 mod world;
 use world::map::Map;
+use world::wave::Waves;
 use world::*;
 
 mod ui;
@@ -32,9 +33,21 @@ use anim::*;
 mod qt;
 use qt::*;
 
+mod debug;
+use debug::DebugOverlay;
+
 const WINDOW_DEFAULT_WIDTH: f64 = 1024.0;
 const WINDOW_DEFAULT_HEIGHT: f64 = 768.0;
 
+/// How many seconds a full day lasts.
+const DAY_LENGTH: f64 = 120.0;
+
+/// How long a message to the player stays up.
+const MESSAGE_TIME: f64 = 3.0;
+
+/// How much of what went into a building the player gets back for selling it.
+const SELL_REFUND: f64 = 0.6;
+
 const MIN_SCALE: f64 = 0.4;
 const MAX_SCALE: f64 = 5.0;
 
@@ -42,30 +55,70 @@ struct Game<'a, 'b, C: CharacterCache> {
     map: Map,
     actors: Vec<Actor>,
     animations: Vec<Animation>,
+    effects: Vec<AreaEffect>,
+    projectiles: Vec<Projectile>,
+    /// Which status the next test field placed with F6 applies.
+    next_status_field: usize,
     hovered_actor: Option<usize>,
     selected_actor: Option<usize>,
+    /// How the selected actor died, kept in the panel until something else is
+    /// selected.
+    obituary: Option<String>,
     mouse: MouseDetails,
     offset: WorldOffset,
-    ui: GUI<'b, C>,
+    ui: Gui<'b, C>,
     name_generator: Generator<'a>,
     paused: bool,
+    debug: DebugOverlay,
+    queue_orders: bool,
+    clock: f64,
+    scripts: Scripts,
+    relations: Relations,
+    /// The state of the tower defense game, when playing one.
+    defense: Option<Defense>,
+    /// What the player is about to build, if anything.
+    building: Option<Structure>,
+    /// Where it would go, following the mouse.
+    blueprint: Option<Blueprint>,
+    ledger: Ledger,
+    /// Something to tell the player, and how much longer to show it for.
+    message: Option<(String, f64)>,
 }
 
 impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
-    pub fn new(font: &'b mut C) -> Self {
+    pub fn new(font: &'b mut C, defense: bool) -> Self {
         let width = 80;
         let height = 100;
         let mut game = Self {
-            map: Map::new(width, height),
+            map: if defense {
+                Map::with_roads(width, height)
+            } else {
+                Map::new(width, height)
+            },
             actors: vec![],
             animations: vec![],
+            effects: vec![],
+            projectiles: vec![],
+            next_status_field: 0,
             hovered_actor: None,
             selected_actor: None,
+            obituary: None,
             mouse: MouseDetails::new(),
             offset: WorldOffset::new(),
-            ui: GUI::new(WINDOW_DEFAULT_WIDTH, WINDOW_DEFAULT_HEIGHT, font),
+            ui: Gui::new(WINDOW_DEFAULT_WIDTH, WINDOW_DEFAULT_HEIGHT, font),
             name_generator: Generator::default(),
             paused: false,
+            debug: DebugOverlay::default(),
+            queue_orders: false,
+            // Start the day off in the morning.
+            clock: DAY_LENGTH / 4.0,
+            scripts: Scripts::load("assets/scripts"),
+            relations: Relations::load("assets/factions.txt"),
+            defense: None,
+            building: None,
+            blueprint: None,
+            ledger: Ledger::new(),
+            message: None,
         };
         game.center_on(
             width / 2,
@@ -73,6 +126,43 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
             WINDOW_DEFAULT_WIDTH,
             WINDOW_DEFAULT_HEIGHT,
         );
+        if defense {
+            game.start_defense();
+        } else {
+            game.populate_sandbox();
+        }
+        game
+    }
+
+    /// Set up a game of tower defense, with the waves read in from a file and
+    /// a couple of towers guarding the goal.
+    fn start_defense(&mut self) {
+        // Everyone needs to be able to see the invaders coming.
+        self.map.fog = false;
+        if let Some((goal_x, goal_y)) = self.map.goal {
+            let size = Structure::Tower.size();
+            for &(cell_x, cell_y) in [(goal_x - 3, goal_y - 3), (goal_x + 3, goal_y + 3)].iter() {
+                let (x, y) = self.map.cell_center(cell_x, cell_y);
+                let corner = match self.map.footprint_at(x, y, size.0, size.1) {
+                    Some(corner) => corner,
+                    None => continue,
+                };
+                // Only where the player could have built one, so the road to
+                // the goal stays open.
+                let cells = Map::cells_in(corner, size);
+                if cells.iter().all(|&(x, y)| self.map.is_buildable(x, y))
+                    && !self.map.cuts_off_goal(&cells)
+                {
+                    self.add_structure(Structure::Tower, corner);
+                }
+            }
+        }
+        self.defense = Some(Defense::new(Waves::load("assets/waves.txt", &self.map)));
+    }
+
+    /// Fill the map with a bit of everything, to watch them get along.
+    fn populate_sandbox(&mut self) {
+        let game = self;
         // TODO: Currently just adds 10 generic actors. Long-term remove this.
         let bounds = game.map.get_bounds();
         let (x_dim, y_dim) = (bounds.w, bounds.h);
@@ -81,55 +171,111 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
             let y: f64 = rand::thread_rng().gen();
             game.add_actor(x * x_dim, y * y_dim, ActorBody::Worker, ActorAi::Wanderer);
         }
-        game.add_actor(50.0, 800.0, ActorBody::Worker, ActorAi::Kamikaze);
-        game.add_actor(50.0, 50.0, ActorBody::Worker, ActorAi::Kamikaze);
-        game.add_actor(800.0, 50.0, ActorBody::Worker, ActorAi::Kamikaze);
-        game.add_actor(800.0, 800.0, ActorBody::Worker, ActorAi::Kamikaze);
-
-        game.add_actor(
-            300.0,
-            300.0,
-            ActorBody::Building,
-            ActorAi::Spawner { rate: 5.0 },
-        );
+        for &(x, y) in [(50.0, 800.0), (50.0, 50.0)].iter() {
+            game.add_actor(x, y, ActorBody::Worker, ActorAi::Kamikaze)
+                .faction = Faction::Raiders;
+        }
+        game.add_actor(400.0, 600.0, ActorBody::Worker, ActorAi::Patroller);
+        game.add_actor(600.0, 400.0, ActorBody::Worker, ActorAi::Patroller);
+        let behaviours = Behaviours::load("assets/ai");
+        for &(x, y, tree) in [
+            (800.0, 50.0, "bomber"),
+            (800.0, 800.0, "bomber"),
+            (200.0, 500.0, "guard"),
+            (500.0, 200.0, "guard"),
+        ]
+        .iter()
+        {
+            if let Some(ai) = behaviours.ai(tree) {
+                let dangerous = ai.is_dangerous();
+                let actor = game.add_actor(x, y, ActorBody::Worker, ai);
+                if dangerous {
+                    actor.faction = Faction::Raiders;
+                }
+            }
+        }
+        for &(x, y) in [(150.0, 150.0), (650.0, 650.0)].iter() {
+            if let Some(ai) = game.scripts.ai("shadow") {
+                game.add_actor(x, y, ActorBody::Worker, ai);
+            }
+        }
 
-        game
+        let (width, height) = Structure::Barracks.size();
+        if let Some(corner) = game.map.footprint_at(300.0, 300.0, width, height) {
+            game.add_structure(Structure::Barracks, corner);
+        }
+        for &(x, y) in [(260.0, 300.0), (340.0, 300.0), (300.0, 340.0)].iter() {
+            game.add_actor(x, y, ActorBody::Worker, ActorAi::Utility(Utility::new()))
+                .faction = Faction::Player;
+        }
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
         let qt = self.build_quadtree();
+        self.scripts.update(args.dt);
         if !self.paused {
+            self.clock += args.dt;
             for animation in self.animations.iter_mut() {
                 animation.update(args.dt);
             }
-            let mut results =
-                Actor::update_all(args.dt, &mut self.actors, &qt, &self.map.get_bounds());
-            if !results.dead_actors.is_empty() {
-                results.dead_actors.sort();
-                for dead_actor_index in results.dead_actors.into_iter().rev() {
-                    if let Some(selected_actor_index) = self.selected_actor {
-                        if selected_actor_index == dead_actor_index {
-                            // If the selected actor is dead, just remove the
-                            // reference.
-                            self.selected_actor = None;
-                        } else if selected_actor_index > dead_actor_index {
-                            // Every time we delete something earlier in the array,
-                            // move our reference back one.
-                            self.selected_actor = Some(selected_actor_index - 1);
-                        }
-                    }
-                    self.actors.remove(dead_actor_index);
-                }
+            let ctx = TaskContext {
+                dt: args.dt,
+                qt: &qt,
+                bounds: &self.map.get_bounds(),
+                map: &self.map,
+                time_of_day: self.time_of_day(),
+                scripts: &self.scripts,
+                relations: &self.relations,
+            };
+            let mut results = Actor::update_all(&mut self.actors, &ctx);
+            self.effects.append(&mut results.new_effects);
+            self.projectiles.append(&mut results.new_projectiles);
+            let shot = Projectile::update_all(&mut self.projectiles, &mut self.actors, args.dt);
+            results.dead_actors.extend(shot);
+            let effect_results = AreaEffect::update_all(&mut self.effects, &mut self.actors, &ctx);
+            results.dead_actors.extend(effect_results.dead_actors);
+            if let Some(defense) = &mut self.defense {
+                let defense_results = defense.update(args.dt, &self.actors, &self.map);
+                results.dead_actors.extend(defense_results.leaked);
+                results.new_actors.extend(defense_results.new_invaders);
+                self.ledger.earn(defense_results.income);
             }
+            self.remove_actors(results.dead_actors);
             for mut actor in results.new_actors.drain(..) {
                 actor.name = self.get_name();
                 self.actors.push(actor);
             }
-            for animation in results.new_animations.drain(..) {
-                self.animations.push(animation);
+            self.animations.extend(effect_results.new_animations);
+        }
+        if let Some((_, remaining)) = &mut self.message {
+            *remaining -= args.dt;
+            if *remaining <= 0.0 {
+                self.message = None;
+            }
+        }
+        self.map.update_visibility(&self.actors);
+        // Lose track of anyone who has wandered off into the fog.
+        if let Some(selected) = self.selected_actor {
+            if !self.is_shown(selected) {
+                self.selected_actor = None;
             }
         }
+        // Actors may have been added or removed, so the old tree can't be
+        // trusted to find who's under the mouse. Hang on to what was asked of
+        // it this tick first, so the overlay can still show it.
+        if self.debug.enabled {
+            self.debug.record_queries(&qt);
+        }
+        let qt = self.build_quadtree();
         self.find_hovered_actor(&qt);
+        let (mouse_x, mouse_y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
+        self.blueprint = self.building.and_then(|structure| {
+            Blueprint::new(structure, mouse_x, mouse_y, &self.map, &qt, &self.ledger)
+        });
+        if self.debug.enabled {
+            self.debug.update(args.dt);
+            self.debug.observe(&qt);
+        }
         self.update_ui();
     }
 
@@ -144,8 +290,24 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
             .scale(self.offset.scaling_factor, self.offset.scaling_factor);
         let (mouse_x, mouse_y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
         self.map.render(world_transform, g, mouse_x, mouse_y);
-        for actor in self.actors.iter() {
-            actor.render(world_transform, g);
+        if let Some(blueprint) = &self.blueprint {
+            blueprint.render(&self.map, world_transform, g);
+        }
+        for effect in self.effects.iter() {
+            let (x, y) = effect.region.center();
+            if self.map.is_visible(x, y) {
+                effect.render(world_transform, g);
+            }
+        }
+        for (i, actor) in self.actors.iter().enumerate() {
+            if self.is_shown(i) {
+                actor.render(world_transform, g);
+            }
+        }
+        for projectile in self.projectiles.iter() {
+            if self.map.is_visible(projectile.x, projectile.y) {
+                projectile.render(world_transform, g);
+            }
         }
         if let Some(actor_index) = self.hovered_actor {
             self.actors[actor_index].render_extras(&self.actors, world_transform, g);
@@ -155,6 +317,11 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
         }
         self.animations
             .retain(|anim| !anim.render(world_transform, g));
+        self.debug.render(world_transform, g);
+        if let Some(actor_index) = self.selected_actor {
+            self.debug
+                .render_sight(actor_index, &self.actors, &self.map, world_transform, g);
+        }
         self.ui.render(self.paused, c, g)
     }
 
@@ -178,14 +345,60 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
 
     pub fn mouse_up(&mut self) {
         if self.mouse.barely_moved() {
-            if !self.ui.handle_click(self.mouse.x, self.mouse.y) {
-                let qt = self.build_quadtree();
-                self.set_selected_actor(&qt);
+            match self.ui.handle_click(self.mouse.x, self.mouse.y) {
+                Click::Missed if self.building.is_some() => self.place_building(),
+                Click::Missed => {
+                    let qt = self.build_quadtree();
+                    self.set_selected_actor(&qt);
+                    self.debug.record_queries(&qt);
+                }
+                Click::Handled => {}
+                Click::Action(PanelAction::Upgrade) => self.upgrade_selected(),
+                Click::Action(PanelAction::Sell) => self.sell_selected(),
+                Click::Action(PanelAction::ChangeTargeting) => self.cycle_target_policy(),
             }
         }
         self.mouse.pressed = false;
     }
 
+    /// Order the selected actor to go wherever was clicked, or to chase down
+    /// whoever was clicked on.
+    pub fn right_click(&mut self) {
+        if self.building.is_some() {
+            self.building = None;
+            self.blueprint = None;
+            return;
+        }
+        let selected = match self.selected_actor {
+            Some(selected) if self.actors[selected].can_move() => selected,
+            _ => return,
+        };
+        let (mouse_x, mouse_y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
+        let qt = self.build_quadtree();
+        let clicked = qt
+            .query(&Region::new_point(mouse_x, mouse_y))
+            .into_iter()
+            .map(|actor_ref| actor_ref.id)
+            .find(|&id| id != selected && self.is_shown(id));
+        self.debug.record_queries(&qt);
+        let task = match clicked {
+            Some(target) => {
+                Task::move_to_actor(Target::new(target, self.actors[target].id), ACTOR_REF_SIZE)
+            }
+            None => {
+                let (x, y) = self.map.get_bounds().constrain(mouse_x, mouse_y);
+                Task::move_to(x, y)
+            }
+        };
+        self.actors[selected].order(task, self.queue_orders);
+    }
+
+    /// While set, orders get added to the end of an actor's queue instead of
+    /// replacing it.
+    pub fn set_queue_orders(&mut self, queue: bool) {
+        self.queue_orders = queue;
+    }
+
     pub fn mouse_scroll(&mut self, up: bool) {
         if !self.ui.handle_scroll(self.mouse.x, self.mouse.y, up) {
             self.offset.zoom(up, self.mouse.x, self.mouse.y);
@@ -196,41 +409,268 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
         self.paused = !self.paused;
     }
 
+    pub fn toggle_debug(&mut self) {
+        self.debug.toggle();
+    }
+
+    pub fn toggle_sight_debug(&mut self) {
+        self.debug.toggle_sight();
+    }
+
+    pub fn toggle_fog(&mut self) {
+        self.map.fog = !self.map.fog;
+    }
+
+    /// Drop a field applying each kind of status in turn.
+    pub fn place_status_field(&mut self) {
+        let kind = StatusKind::ALL[self.next_status_field];
+        self.next_status_field = (self.next_status_field + 1) % StatusKind::ALL.len();
+        let strength = match kind {
+            StatusKind::Burn => 4.0,
+            _ => 0.5,
+        };
+        self.place_effect(Payload::Status {
+            kind,
+            strength,
+            duration: 1.0,
+        });
+    }
+
+    /// Drop an effect on the spot under the mouse, for trying effects out.
+    pub fn place_effect(&mut self, payload: Payload) {
+        let (x, y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
+        let effect = AreaEffect::new(Region::new_circle(x, y, 40.0), payload, None);
+        self.effects.push(match effect.payload {
+            Payload::Push { .. } => effect.lasting(1.0, 0.25),
+            _ => effect.lasting(5.0, 0.5),
+        });
+    }
+
+    /// Start laying out a new structure to be placed with a click, or switch
+    /// to the next kind if already building.
+    pub fn cycle_building(&mut self) {
+        self.building = Some(match self.building {
+            Some(structure) => structure.next(),
+            None => Structure::Tower,
+        });
+    }
+
+    /// Put up whatever is being built where the mouse is, as long as it's
+    /// allowed there.
+    fn place_building(&mut self) {
+        let blueprint = match self.blueprint.take() {
+            Some(blueprint) => blueprint,
+            None => return,
+        };
+        let structure = blueprint.structure;
+        if let Some(problem) = blueprint.problem {
+            self.show_message(format!("Can't build a {} here: {}", structure, problem));
+            return;
+        }
+        if let Err(short) = self.ledger.spend(structure.cost()) {
+            self.show_message(format!(
+                "Can't afford a {}: you need {} more money",
+                structure, short
+            ));
+            return;
+        }
+        self.add_structure(structure, blueprint.corner);
+        self.building = None;
+    }
+
+    /// Put up `structure` with the top left of its footprint at `corner`,
+    /// taking up the cells it covers.
+    fn add_structure(&mut self, structure: Structure, corner: (usize, usize)) {
+        let mut actor = structure.build(corner, &self.map);
+        actor.name = self.get_name();
+        self.actors.push(actor);
+        self.map
+            .occupy(&Map::cells_in(corner, structure.size()), true);
+        self.reroute_invaders();
+    }
+
+    /// Take the actors at `indices` out of the world, paying out bounties and
+    /// freeing up the ground under any buildings among them.
+    fn remove_actors(&mut self, mut indices: Vec<usize>) {
+        if indices.is_empty() {
+            return;
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        for dead_actor_index in indices.into_iter().rev() {
+            if let Some(selected_actor_index) = self.selected_actor {
+                if selected_actor_index == dead_actor_index {
+                    // If the selected actor is dead, remove the
+                    // reference and say what happened to it instead.
+                    self.selected_actor = None;
+                    let actor = &self.actors[dead_actor_index];
+                    self.obituary = actor.death.as_ref().map(|death| {
+                        format!(
+                            "{}\n\n{}",
+                            Actor::description(dead_actor_index, &self.actors),
+                            death
+                        )
+                    });
+                } else if selected_actor_index > dead_actor_index {
+                    // Every time we delete something earlier in the array,
+                    // move our reference back one.
+                    self.selected_actor = Some(selected_actor_index - 1);
+                }
+            }
+            // The hovered actor is only worked out again on the next update,
+            // so it has to be kept in step too.
+            self.hovered_actor = match self.hovered_actor {
+                Some(hovered) if hovered == dead_actor_index => None,
+                Some(hovered) if hovered > dead_actor_index => Some(hovered - 1),
+                hovered => hovered,
+            };
+            self.pay_bounty(dead_actor_index);
+            self.clear_footprint(dead_actor_index);
+            self.actors.remove(dead_actor_index);
+        }
+        Actor::fix_targets(&mut self.actors);
+        for projectile in self.projectiles.iter_mut() {
+            projectile.fix_targets(&self.actors);
+        }
+    }
+
+    /// The selected actor, if it's a building the player owns.
+    fn selected_building(&self) -> Option<(usize, Structure, usize)> {
+        let selected = self.selected_actor?;
+        let actor = &self.actors[selected];
+        match actor.body() {
+            ActorBody::Building(structure, tier) if actor.faction == Faction::Player => {
+                Some((selected, structure, tier))
+            }
+            _ => None,
+        }
+    }
+
+    /// How much the player would get back for selling a `structure` built
+    /// up to `tier`.
+    fn refund(structure: Structure, tier: usize) -> u32 {
+        (structure.invested(tier) as f64 * SELL_REFUND).floor() as u32
+    }
+
+    /// Pay for the selected building to go up a tier, if it can.
+    fn upgrade_selected(&mut self) {
+        let (selected, structure, tier) = match self.selected_building() {
+            Some(building) => building,
+            None => return,
+        };
+        let cost = match structure.upgrade_cost(tier) {
+            Some(cost) => cost,
+            None => return,
+        };
+        match self.ledger.spend(cost) {
+            Ok(()) => self.actors[selected].upgrade(),
+            Err(short) => self.show_message(format!(
+                "Can't afford to upgrade the {}: you need {} more money",
+                structure, short
+            )),
+        }
+    }
+
+    /// Knock down the selected building and give the player back some of what
+    /// they put into it.
+    fn sell_selected(&mut self) {
+        if let Some((selected, structure, tier)) = self.selected_building() {
+            self.ledger.earn(Self::refund(structure, tier));
+            self.remove_actors(vec![selected]);
+        }
+    }
+
+    /// Pay the player for actor `i` if one of theirs killed it, and credit
+    /// the kill to whoever did it.
+    fn pay_bounty(&mut self, i: usize) {
+        let id = match self.ledger.pay_bounty(&self.actors[i]) {
+            Some(id) => id,
+            None => return,
+        };
+        if let Some(killer) = self.actors.iter_mut().find(|actor| actor.id == id) {
+            killer.kills += 1;
+        }
+    }
+
+    /// Put up a message for the player for a few seconds.
+    fn show_message(&mut self, message: String) {
+        self.message = Some((message, MESSAGE_TIME));
+    }
+
+    /// Free up the cells under actor `i` if it's a building, since it's about
+    /// to be removed.
+    fn clear_footprint(&mut self, i: usize) {
+        let actor = &self.actors[i];
+        if let (Some(corner), Some(size)) = (actor.corner, actor.body().footprint()) {
+            self.map.occupy(&Map::cells_in(corner, size), false);
+            self.reroute_invaders();
+        }
+    }
+
+    /// Have every invader work out its way to the goal again, now that the
+    /// roads have changed.
+    fn reroute_invaders(&mut self) {
+        for actor in self.actors.iter_mut() {
+            if let ActorAi::Invader = actor.ai {
+                actor.tasks.clear();
+            }
+        }
+    }
+
+    /// Switch the selected tower over to the next way of picking targets.
+    pub fn cycle_target_policy(&mut self) {
+        if let Some(selected) = self.selected_actor {
+            if let ActorAi::Tower(policy) = &mut self.actors[selected].ai {
+                *policy = policy.next();
+            }
+        }
+    }
+
+    /// Skip the rest of the countdown and send in the next wave.
+    pub fn call_wave_early(&mut self) {
+        if let Some(defense) = &mut self.defense {
+            defense.call_wave_early();
+        }
+    }
+
     pub fn resize(&mut self, w: f64, h: f64) {
         self.ui.resize(w, h);
     }
 
     fn find_hovered_actor(&mut self, qt: &QuadTree<ActorRef>) {
-        let (mouse_x, mouse_y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
-        let region = Region::new_point(mouse_x, mouse_y);
-        let results = qt.query(&region);
-        if results.is_empty() {
-            // If we're not intersescting anything.
-            self.hovered_actor = None;
-        } else {
-            if let Some(new_hovered) = results.first().map(|actor_ref| actor_ref.id) {
-                self.hovered_actor = Some(new_hovered);
-            }
-        }
+        self.hovered_actor = self.actor_under_mouse(qt);
     }
 
     fn set_selected_actor(&mut self, qt: &QuadTree<ActorRef>) {
+        self.selected_actor = self.actor_under_mouse(qt);
+        self.obituary = None;
+    }
+
+    /// The first actor under the mouse cursor, ignoring anything hidden in
+    /// the fog.
+    fn actor_under_mouse(&self, qt: &QuadTree<ActorRef>) -> Option<usize> {
         let (mouse_x, mouse_y) = self.offset.to_local_pixel(self.mouse.x, self.mouse.y);
         let region = Region::new_point(mouse_x, mouse_y);
-        let results = qt.query(&region);
-        if results.is_empty() {
-            self.selected_actor = None;
-        } else {
-            if let Some(new_selected) = results.first().map(|actor_ref| actor_ref.id) {
-                self.selected_actor = Some(new_selected);
-            }
-        }
+        qt.query(&region)
+            .into_iter()
+            .map(|actor_ref| actor_ref.id)
+            .find(|&id| self.is_shown(id))
+    }
+
+    /// The player always knows where their own actors are, but anyone else
+    /// only shows up while one of them can see it.
+    fn is_shown(&self, i: usize) -> bool {
+        let actor = &self.actors[i];
+        actor.faction == Faction::Player || self.map.is_visible(actor.x, actor.y)
     }
 
     fn build_quadtree(&self) -> QuadTree<ActorRef> {
         let bounds = self.map.get_bounds();
         let mut qt: QuadTree<ActorRef> =
             QuadTree::new(RectangleData::new(bounds.x, bounds.y, bounds.w, bounds.h));
+        if self.debug.enabled {
+            qt.record_queries();
+        }
         for (i, actor) in self.actors.iter().enumerate() {
             qt.insert(actor.get_ref(i));
         }
@@ -247,10 +687,16 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
         self.offset.slide(target_x - global_x, target_y - global_y);
     }
 
-    fn add_actor(&mut self, x: f64, y: f64, body: ActorBody, ai: ActorAi) {
+    fn add_actor(&mut self, x: f64, y: f64, body: ActorBody, ai: ActorAi) -> &mut Actor {
         let mut new_actor = Actor::new(x, y, body, ai);
         new_actor.name = self.get_name();
         self.actors.push(new_actor);
+        self.actors.last_mut().unwrap()
+    }
+
+    /// How far through the current day it is, from 0 at midnight up to 1.
+    fn time_of_day(&self) -> f64 {
+        (self.clock / DAY_LENGTH).fract()
     }
 
     fn get_name(&mut self) -> Option<String> {
@@ -261,6 +707,8 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
         if let Some(selected) = self.selected_actor {
             self.ui
                 .selected_desc(Actor::description(selected, &self.actors).as_str());
+        } else if let Some(obituary) = &self.obituary {
+            self.ui.selected_desc(obituary);
         } else {
             self.ui.selected_desc("");
         }
@@ -270,10 +718,47 @@ impl<'a, 'b, C: CharacterCache> Game<'a, 'b, C> {
         } else {
             self.ui.hovered_desc("");
         }
+        let mut actions = vec![];
+        if let Some((selected, structure, tier)) = self.selected_building() {
+            if let Some(cost) = structure.upgrade_cost(tier) {
+                actions.push((PanelAction::Upgrade, format!("Upgrade ({})", cost)));
+            }
+            actions.push((
+                PanelAction::Sell,
+                format!("Sell (+{})", Self::refund(structure, tier)),
+            ));
+            if let ActorAi::Tower(policy) = &self.actors[selected].ai {
+                actions.push((
+                    PanelAction::ChangeTargeting,
+                    format!("Targeting: {}", policy),
+                ));
+            }
+        }
+        self.ui.selected_actions(&actions);
         self.ui.mouse_pos(self.mouse.x, self.mouse.y);
+        let mut hud = vec![self.ledger.description()];
+        if let Some(defense) = &self.defense {
+            hud.push(defense.description());
+            let outcome = defense.outcome.map(|outcome| outcome.to_string());
+            self.ui.banner(outcome.as_deref());
+        }
+        if let Some(structure) = self.building {
+            hud.push(format!(
+                "Building a {} (B for something else, right-click to cancel)",
+                structure
+            ));
+            if let Some(problem) = self.blueprint.as_ref().and_then(|b| b.problem) {
+                hud.push(format!("Can't build here: {}", problem));
+            }
+        }
+        if let Some((message, _)) = &self.message {
+            hud.push(message.clone());
+        }
+        self.ui.hud(&hud.join("\n"));
     }
 }
 
+#[derive(Default)]
 pub struct WorldOffset {
     v: f64,              // vertical offset
     h: f64,              // horizontal offset
@@ -283,9 +768,8 @@ pub struct WorldOffset {
 impl WorldOffset {
     pub fn new() -> Self {
         WorldOffset {
-            v: 0.0,
-            h: 0.0,
             scaling_factor: 1.0,
+            ..Default::default()
         }
     }
 
@@ -383,7 +867,7 @@ impl MouseDetails {
 }
 
 fn main() {
-    let ref mut window: Sdl2Window = WindowSettings::new(
+    let mut window: Sdl2Window = WindowSettings::new(
         "Simulation Thing",
         (WINDOW_DEFAULT_WIDTH, WINDOW_DEFAULT_HEIGHT),
     )
@@ -396,23 +880,26 @@ fn main() {
         GlyphCache::new("assets/OpenSans-Regular.ttf", (), TextureSettings::new()).unwrap();
 
     let mut gl = GlGraphics::new(OpenGL::V4_5);
-    let mut game = Game::new(&mut glyph_cache);
+    // Tower defense with `--defense`, otherwise a sandbox to watch the AI in.
+    let defense = std::env::args().any(|arg| arg == "--defense");
+    let mut game = Game::new(&mut glyph_cache, defense);
     let mut events = Events::new(EventSettings::new());
 
-    while let Some(e) = events.next(window) {
+    while let Some(e) = events.next(&mut window) {
         e.update(|args| {
             game.update(args);
         });
 
         e.render(|args| {
-            gl.draw(args.viewport(), |c, g| match game.render(c, g) {
-                Err(_) => eprintln!("Issue rendering text!"),
-                _ => (),
+            gl.draw(args.viewport(), |c, g| {
+                if game.render(c, g).is_err() {
+                    eprintln!("Issue rendering text!");
+                }
             })
         });
 
         e.mouse_scroll(|args| {
-            game.mouse_scroll(if args[1] > 0.0 { true } else { false });
+            game.mouse_scroll(args[1] > 0.0);
         });
 
         e.mouse_cursor(|args| {
@@ -423,9 +910,39 @@ fn main() {
             Button::Mouse(MouseButton::Left) => {
                 game.mouse_down();
             }
+            Button::Mouse(MouseButton::Right) => {
+                game.right_click();
+            }
+            Button::Keyboard(Key::LShift) | Button::Keyboard(Key::RShift) => {
+                game.set_queue_orders(true);
+            }
             Button::Keyboard(Key::Space) => {
                 game.toggle_pause();
             }
+            Button::Keyboard(Key::N) => {
+                game.call_wave_early();
+            }
+            Button::Keyboard(Key::B) => {
+                game.cycle_building();
+            }
+            Button::Keyboard(Key::T) => {
+                game.cycle_target_policy();
+            }
+            Button::Keyboard(Key::F3) => {
+                game.toggle_debug();
+            }
+            Button::Keyboard(Key::F4) => {
+                game.toggle_sight_debug();
+            }
+            Button::Keyboard(Key::F5) => {
+                game.toggle_fog();
+            }
+            Button::Keyboard(Key::F6) => {
+                game.place_status_field();
+            }
+            Button::Keyboard(Key::F7) => {
+                game.place_effect(Payload::Push { distance: 10.0 });
+            }
             _ => {}
         });
 
@@ -433,6 +950,9 @@ fn main() {
             Button::Mouse(MouseButton::Left) => {
                 game.mouse_up();
             }
+            Button::Keyboard(Key::LShift) | Button::Keyboard(Key::RShift) => {
+                game.set_queue_orders(false);
+            }
             _ => {}
         });
 
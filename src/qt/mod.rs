@@ -1,18 +1,109 @@
+use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
 
-const SPLIT_SIZE: usize = 10;
+pub const SPLIT_SIZE: usize = 10;
 
+/// Refers to an item stored in a `QuadTree`. Only valid for the tree that
+/// handed it out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle(usize);
+
+/// Items are kept once in a backing store, and the nodes of the tree only hold
+/// handles into it, so an item that straddles several nodes is never copied.
 #[derive(Debug)]
-pub struct QuadTree<T: HasRegion + Copy> {
-    contents: Vec<T>,
-    split: bool,
-    children: Option<Box<[QuadTree<T>; 4]>>,
-    bounds: RectangleData,
+pub struct QuadTree<T: HasRegion> {
+    items: Vec<T>,
+    // Cached alongside the items so the nodes don't need to ask for them again.
+    regions: Vec<Region>,
+    root: Node,
+    query_log: Option<RefCell<Vec<Region>>>,
 }
 
-impl<T: HasRegion + Copy> QuadTree<T> {
+impl<T: HasRegion> QuadTree<T> {
     pub fn new(bounds: RectangleData) -> Self {
         QuadTree {
+            items: vec![],
+            regions: vec![],
+            root: Node::new(bounds),
+            query_log: None,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle {
+        let handle = Handle(self.items.len());
+        self.regions.push(value.get_region());
+        self.items.push(value);
+        self.root.insert(handle, &self.regions);
+        handle
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.items.get(handle.0)
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Every item intersecting `region`, each only once and in the order they
+    /// were inserted.
+    pub fn query(&self, region: &Region) -> Vec<&T> {
+        self.query_handles(region)
+            .into_iter()
+            .map(|handle| &self.items[handle.0])
+            .collect()
+    }
+
+    pub fn query_handles(&self, region: &Region) -> Vec<Handle> {
+        if let Some(ref log) = self.query_log {
+            log.borrow_mut().push(region.clone());
+        }
+        let mut found = vec![];
+        self.root.query(region, &self.regions, &mut found);
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Start keeping track of every region this tree is queried with, to be
+    /// collected later with `take_queries`.
+    pub fn record_queries(&mut self) {
+        self.query_log = Some(RefCell::new(vec![]));
+    }
+
+    pub fn take_queries(&self) -> Vec<Region> {
+        match self.query_log {
+            Some(ref log) => log.replace(vec![]),
+            None => vec![],
+        }
+    }
+
+    /// The bounds of every leaf node along with how many items it holds.
+    pub fn leaves(&self) -> Vec<(RectangleData, usize)> {
+        let mut leaves = vec![];
+        self.root.leaves(&mut leaves);
+        leaves
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    contents: Vec<Handle>,
+    split: bool,
+    children: Option<Box<[Node; 4]>>,
+    bounds: RectangleData,
+}
+
+impl Node {
+    fn new(bounds: RectangleData) -> Self {
+        Node {
             contents: vec![],
             split: false,
             children: None,
@@ -20,61 +111,64 @@ impl<T: HasRegion + Copy> QuadTree<T> {
         }
     }
 
-    pub fn insert(&mut self, value: T) {
+    fn insert(&mut self, handle: Handle, regions: &[Region]) {
         if self.split {
-            self.insert_children(value);
+            self.insert_children(handle, regions);
         } else if self.contents.len() >= SPLIT_SIZE {
-            self.split();
-            self.insert_children(value);
-        } else {
-            if self.bounds.intersects(&value) {
-                self.contents.push(value);
-            }
+            self.split(regions);
+            self.insert_children(handle, regions);
+        } else if self.bounds.intersects(&regions[handle.0]) {
+            self.contents.push(handle);
         }
     }
 
-    pub fn query(&self, region: &Region) -> Vec<T> {
-        let mut found: Vec<T> = vec![];
+    fn query(&self, region: &Region, regions: &[Region], found: &mut Vec<Handle>) {
         if self.split {
             let children = self.children.as_ref().unwrap().deref();
-            for i in 0..4 {
-                for child_item in children[i].query(region) {
-                    found.push(child_item);
-                }
+            for child in children.iter() {
+                child.query(region, regions, found);
             }
         } else if self.bounds.intersects(region) {
-            for item in self.contents.iter() {
-                if region.intersects(item) {
-                    found.push(*item);
+            for handle in self.contents.iter() {
+                if region.intersects(&regions[handle.0]) {
+                    found.push(*handle);
                 }
             }
         }
-        found
     }
 
-    fn split(&mut self) {
+    fn leaves(&self, leaves: &mut Vec<(RectangleData, usize)>) {
+        match self.children {
+            Some(ref children) if self.split => {
+                for child in children.iter() {
+                    child.leaves(leaves);
+                }
+            }
+            _ => leaves.push((self.bounds, self.contents.len())),
+        }
+    }
+
+    fn split(&mut self, regions: &[Region]) {
         let (q1, q2, q3, q4) = self.child_coords();
         self.children = Some(Box::new([
-            QuadTree::new(q1),
-            QuadTree::new(q2),
-            QuadTree::new(q3),
-            QuadTree::new(q4),
+            Node::new(q1),
+            Node::new(q2),
+            Node::new(q3),
+            Node::new(q4),
         ]));
         self.split = true;
-        let contents_copy = self.contents.clone();
-        for item in contents_copy {
-            self.insert_children(item);
+        for handle in std::mem::take(&mut self.contents) {
+            self.insert_children(handle, regions);
         }
-        self.contents.clear();
     }
 
-    fn insert_children(&mut self, value: T) {
+    fn insert_children(&mut self, handle: Handle, regions: &[Region]) {
         if self.children.is_none() {
             return;
         }
         let children = self.children.as_mut().unwrap().deref_mut();
-        for i in 0..4 {
-            children[i].insert(value);
+        for child in children.iter_mut() {
+            child.insert(handle, regions);
         }
     }
 
@@ -112,7 +206,7 @@ pub struct PointData {
 
 impl HasRegion for PointData {
     fn get_region(&self) -> Region {
-        Region::Point(self.clone())
+        Region::Point(*self)
     }
 }
 
@@ -120,6 +214,33 @@ impl PointData {
     pub fn new(x: f64, y: f64) -> Self {
         Self { x, y }
     }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        RectangleData::new(self.x, self.y, 0.0, 0.0)
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        0.0
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -132,7 +253,7 @@ pub struct RectangleData {
 
 impl HasRegion for RectangleData {
     fn get_region(&self) -> Region {
-        Region::Rectangle(self.clone())
+        Region::Rectangle(*self)
     }
 }
 
@@ -140,6 +261,41 @@ impl RectangleData {
     pub fn new(x: f64, y: f64, w: f64, h: f64) -> Self {
         Self { x, y, w, h }
     }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn w(&self) -> f64 {
+        self.w
+    }
+
+    pub fn h(&self) -> f64 {
+        self.h
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        *self
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        self.w * self.h
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -151,7 +307,7 @@ pub struct CircleData {
 
 impl HasRegion for CircleData {
     fn get_region(&self) -> Region {
-        Region::Circle(self.clone())
+        Region::Circle(*self)
     }
 }
 
@@ -159,13 +315,228 @@ impl CircleData {
     pub fn new(x: f64, y: f64, r: f64) -> Self {
         Self { x, y, r }
     }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn r(&self) -> f64 {
+        self.r
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        RectangleData::new(self.x - self.r, self.y - self.r, self.r * 2.0, self.r * 2.0)
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        std::f64::consts::PI * self.r.powi(2)
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
 }
 
+#[derive(Debug, Clone)]
+pub struct PolygonData {
+    points: Vec<PointData>,
+}
+
+impl HasRegion for PolygonData {
+    fn get_region(&self) -> Region {
+        Region::Polygon(self.clone())
+    }
+}
+
+impl PolygonData {
+    /// Points must describe a convex polygon, in either winding order.
+    /// Returns `None` if there are fewer than three of them.
+    #[cfg(test)]
+    pub fn new(points: Vec<PointData>) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+        Some(Self { points })
+    }
+
+    pub fn points(&self) -> &[PointData] {
+        &self.points
+    }
+
+    #[cfg(test)]
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        math::convex_contains_point(&math::polygon_vertices(self), x, y)
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        math::vertices_bounding_box(&math::polygon_vertices(self))
+    }
+
+    /// The centroid of the polygon's area, not just the average of its points.
+    pub fn center(&self) -> (f64, f64) {
+        let (mut cx, mut cy, mut double_area) = (0.0, 0.0, 0.0);
+        for (i, a) in self.points.iter().enumerate() {
+            let b = &self.points[(i + 1) % self.points.len()];
+            let cross = a.x * b.y - b.x * a.y;
+            double_area += cross;
+            cx += (a.x + b.x) * cross;
+            cy += (a.y + b.y) * cross;
+        }
+        if double_area == 0.0 {
+            // Degenerate polygon with no area, so fall back to the bounding box.
+            return self.bounding_box().center();
+        }
+        (cx / (3.0 * double_area), cy / (3.0 * double_area))
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        let mut double_area = 0.0;
+        for (i, a) in self.points.iter().enumerate() {
+            let b = &self.points[(i + 1) % self.points.len()];
+            double_area += a.x * b.y - b.x * a.y;
+        }
+        double_area.abs() / 2.0
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        for point in self.points.iter_mut() {
+            point.translate(dx, dy);
+        }
+    }
+}
+
+/// A line segment from (x1, y1) to (x2, y2) swept by a circle of radius `r`.
 #[derive(Debug, Clone, Copy)]
+pub struct CapsuleData {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    r: f64,
+}
+
+impl HasRegion for CapsuleData {
+    fn get_region(&self) -> Region {
+        Region::Capsule(*self)
+    }
+}
+
+impl CapsuleData {
+    #[cfg(test)]
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64, r: f64) -> Self {
+        Self { x1, y1, x2, y2, r }
+    }
+
+    pub fn start(&self) -> (f64, f64) {
+        (self.x1, self.y1)
+    }
+
+    pub fn end(&self) -> (f64, f64) {
+        (self.x2, self.y2)
+    }
+
+    pub fn r(&self) -> f64 {
+        self.r
+    }
+
+    #[cfg(test)]
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        math::point_intersects_capsule(&PointData::new(x, y), self)
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        let (min_x, min_y) = (self.x1.min(self.x2) - self.r, self.y1.min(self.y2) - self.r);
+        let (max_x, max_y) = (self.x1.max(self.x2) + self.r, self.y1.max(self.y2) + self.r);
+        RectangleData::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        ((self.x1 + self.x2) / 2.0, (self.y1 + self.y2) / 2.0)
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        let length = ((self.x2 - self.x1).powi(2) + (self.y2 - self.y1).powi(2)).sqrt();
+        length * self.r * 2.0 + std::f64::consts::PI * self.r.powi(2)
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.x1 += dx;
+        self.y1 += dy;
+        self.x2 += dx;
+        self.y2 += dy;
+    }
+}
+
+/// A rectangle centered on (x, y) and rotated by `angle` radians.
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedRectangleData {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    angle: f64,
+}
+
+impl HasRegion for OrientedRectangleData {
+    fn get_region(&self) -> Region {
+        Region::OrientedRectangle(*self)
+    }
+}
+
+impl OrientedRectangleData {
+    #[cfg(test)]
+    pub fn new(x: f64, y: f64, w: f64, h: f64, angle: f64) -> Self {
+        Self { x, y, w, h, angle }
+    }
+
+    /// Corners in order around the rectangle, useful for rendering.
+    pub fn corners(&self) -> Vec<(f64, f64)> {
+        math::oriented_rectangle_vertices(self)
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
+        math::vertices_bounding_box(&math::oriented_rectangle_vertices(self))
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        self.w * self.h
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Region {
     Point(PointData),
     Rectangle(RectangleData),
     Circle(CircleData),
+    Polygon(PolygonData),
+    Capsule(CapsuleData),
+    OrientedRectangle(OrientedRectangleData),
 }
 
 impl HasRegion for Region {
@@ -176,35 +547,106 @@ impl HasRegion for Region {
 
 impl Region {
     pub fn intersects<H: HasRegion>(&self, h: &H) -> bool {
+        use Region::*;
         let other = h.get_region();
+        match (self, &other) {
+            (Point(a), Point(b)) => math::point_intersects_point(a, b),
+            (Point(a), Rectangle(b)) => math::point_intersects_rectangle(a, b),
+            (Point(a), Circle(b)) => math::point_intersects_circle(a, b),
+            (Point(a), Polygon(b)) => math::point_intersects_polygon(a, b),
+            (Point(a), Capsule(b)) => math::point_intersects_capsule(a, b),
+            (Point(a), OrientedRectangle(b)) => math::point_intersects_oriented_rectangle(a, b),
+            (Rectangle(a), Point(b)) => math::rectangle_intersects_point(a, b),
+            (Rectangle(a), Rectangle(b)) => math::rectangle_intersects_rectangle(a, b),
+            (Rectangle(a), Circle(b)) => math::rectangle_intersects_circle(a, b),
+            (Rectangle(a), Polygon(b)) => math::rectangle_intersects_polygon(a, b),
+            (Rectangle(a), Capsule(b)) => math::rectangle_intersects_capsule(a, b),
+            (Rectangle(a), OrientedRectangle(b)) => {
+                math::rectangle_intersects_oriented_rectangle(a, b)
+            }
+            (Circle(a), Point(b)) => math::circle_intersects_point(a, b),
+            (Circle(a), Rectangle(b)) => math::circle_intersects_rectangle(a, b),
+            (Circle(a), Circle(b)) => math::circle_intersects_circle(a, b),
+            (Circle(a), Polygon(b)) => math::circle_intersects_polygon(a, b),
+            (Circle(a), Capsule(b)) => math::circle_intersects_capsule(a, b),
+            (Circle(a), OrientedRectangle(b)) => math::circle_intersects_oriented_rectangle(a, b),
+            (Polygon(a), Point(b)) => math::polygon_intersects_point(a, b),
+            (Polygon(a), Rectangle(b)) => math::polygon_intersects_rectangle(a, b),
+            (Polygon(a), Circle(b)) => math::polygon_intersects_circle(a, b),
+            (Polygon(a), Polygon(b)) => math::polygon_intersects_polygon(a, b),
+            (Polygon(a), Capsule(b)) => math::polygon_intersects_capsule(a, b),
+            (Polygon(a), OrientedRectangle(b)) => math::polygon_intersects_oriented_rectangle(a, b),
+            (Capsule(a), Point(b)) => math::capsule_intersects_point(a, b),
+            (Capsule(a), Rectangle(b)) => math::capsule_intersects_rectangle(a, b),
+            (Capsule(a), Circle(b)) => math::capsule_intersects_circle(a, b),
+            (Capsule(a), Polygon(b)) => math::capsule_intersects_polygon(a, b),
+            (Capsule(a), Capsule(b)) => math::capsule_intersects_capsule(a, b),
+            (Capsule(a), OrientedRectangle(b)) => math::capsule_intersects_oriented_rectangle(a, b),
+            (OrientedRectangle(a), Point(b)) => math::oriented_rectangle_intersects_point(a, b),
+            (OrientedRectangle(a), Rectangle(b)) => {
+                math::oriented_rectangle_intersects_rectangle(a, b)
+            }
+            (OrientedRectangle(a), Circle(b)) => math::oriented_rectangle_intersects_circle(a, b),
+            (OrientedRectangle(a), Polygon(b)) => math::oriented_rectangle_intersects_polygon(a, b),
+            (OrientedRectangle(a), Capsule(b)) => math::oriented_rectangle_intersects_capsule(a, b),
+            (OrientedRectangle(a), OrientedRectangle(b)) => {
+                math::oriented_rectangle_intersects_oriented_rectangle(a, b)
+            }
+        }
+    }
+
+    /// Whether `h` lies entirely within this region. Every region is convex, so
+    /// it's enough to check the corners (or end circles) of the other shape.
+    #[cfg(test)]
+    pub fn contains<H: HasRegion>(&self, h: &H) -> bool {
+        math::region_primitives(&h.get_region())
+            .iter()
+            .all(|primitive| math::region_contains_primitive(self, primitive))
+    }
+
+    pub fn bounding_box(&self) -> RectangleData {
         match self {
-            Region::Point(self_data) => match other {
-                Region::Point(other_data) => math::point_intersects_point(self_data, &other_data),
-                Region::Rectangle(other_data) => {
-                    math::point_intersects_rectangle(self_data, &other_data)
-                }
-                Region::Circle(other_data) => math::point_intersects_circle(self_data, &other_data),
-            },
-            Region::Rectangle(self_data) => match other {
-                Region::Point(other_data) => {
-                    math::rectangle_intersects_point(self_data, &other_data)
-                }
-                Region::Rectangle(other_data) => {
-                    math::rectangle_intersects_rectangle(self_data, &other_data)
-                }
-                Region::Circle(other_data) => {
-                    math::rectangle_intersects_circle(self_data, &other_data)
-                }
-            },
-            Region::Circle(self_data) => match other {
-                Region::Point(other_data) => math::circle_intersects_point(self_data, &other_data),
-                Region::Rectangle(other_data) => {
-                    math::circle_intersects_rectangle(self_data, &other_data)
-                }
-                Region::Circle(other_data) => {
-                    math::circle_intersects_circle(self_data, &other_data)
-                }
-            },
+            Region::Point(data) => data.bounding_box(),
+            Region::Rectangle(data) => data.bounding_box(),
+            Region::Circle(data) => data.bounding_box(),
+            Region::Polygon(data) => data.bounding_box(),
+            Region::Capsule(data) => data.bounding_box(),
+            Region::OrientedRectangle(data) => data.bounding_box(),
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        match self {
+            Region::Point(data) => data.center(),
+            Region::Rectangle(data) => data.center(),
+            Region::Circle(data) => data.center(),
+            Region::Polygon(data) => data.center(),
+            Region::Capsule(data) => data.center(),
+            Region::OrientedRectangle(data) => data.center(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn area(&self) -> f64 {
+        match self {
+            Region::Point(data) => data.area(),
+            Region::Rectangle(data) => data.area(),
+            Region::Circle(data) => data.area(),
+            Region::Polygon(data) => data.area(),
+            Region::Capsule(data) => data.area(),
+            Region::OrientedRectangle(data) => data.area(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        match self {
+            Region::Point(data) => data.translate(dx, dy),
+            Region::Rectangle(data) => data.translate(dx, dy),
+            Region::Circle(data) => data.translate(dx, dy),
+            Region::Polygon(data) => data.translate(dx, dy),
+            Region::Capsule(data) => data.translate(dx, dy),
+            Region::OrientedRectangle(data) => data.translate(dx, dy),
         }
     }
 
@@ -219,10 +661,41 @@ impl Region {
     pub fn new_circle(x: f64, y: f64, r: f64) -> Self {
         Region::Circle(CircleData::new(x, y, r))
     }
+
+    /// Returns `None` if there are fewer than three points.
+    #[cfg(test)]
+    pub fn new_polygon(points: &[(f64, f64)]) -> Option<Self> {
+        PolygonData::new(points.iter().map(|&(x, y)| PointData::new(x, y)).collect())
+            .map(Region::Polygon)
+    }
+
+    #[cfg(test)]
+    pub fn new_capsule(x1: f64, y1: f64, x2: f64, y2: f64, r: f64) -> Self {
+        Region::Capsule(CapsuleData::new(x1, y1, x2, y2, r))
+    }
+
+    #[cfg(test)]
+    pub fn new_oriented_rect(x: f64, y: f64, w: f64, h: f64, angle: f64) -> Self {
+        Region::OrientedRectangle(OrientedRectangleData::new(x, y, w, h, angle))
+    }
 }
 
 mod math {
-    use super::{CircleData, PointData, RectangleData};
+    #[cfg(test)]
+    use super::Region;
+    use super::{
+        CapsuleData, CircleData, OrientedRectangleData, PointData, PolygonData, RectangleData,
+    };
+
+    type Vertex = (f64, f64);
+
+    /// The pieces a region can be reduced to for containment checks: a convex
+    /// container holds a region iff it holds all of these.
+    #[cfg(test)]
+    pub enum Primitive {
+        Point(Vertex),
+        Circle(Vertex, f64),
+    }
 
     pub fn approx_eq(v1: f64, v2: f64) -> bool {
         const POINT_DELTA: f64 = 0.001;
@@ -289,6 +762,361 @@ mod math {
     pub fn circle_intersects_rectangle(c: &CircleData, r: &RectangleData) -> bool {
         rectangle_intersects_circle(r, c)
     }
+
+    pub fn point_intersects_polygon(p: &PointData, poly: &PolygonData) -> bool {
+        convex_contains_point(&polygon_vertices(poly), p.x, p.y)
+    }
+
+    pub fn polygon_intersects_point(poly: &PolygonData, p: &PointData) -> bool {
+        point_intersects_polygon(p, poly)
+    }
+
+    pub fn point_intersects_capsule(p: &PointData, cap: &CapsuleData) -> bool {
+        segment_distance_sq_to_point((cap.x1, cap.y1), (cap.x2, cap.y2), (p.x, p.y))
+            <= cap.r.powi(2)
+    }
+
+    pub fn capsule_intersects_point(cap: &CapsuleData, p: &PointData) -> bool {
+        point_intersects_capsule(p, cap)
+    }
+
+    pub fn point_intersects_oriented_rectangle(p: &PointData, o: &OrientedRectangleData) -> bool {
+        // Rotate the point into the rectangle's frame of reference, where the
+        // check becomes a simple axis-aligned one.
+        let (sin, cos) = (-o.angle).sin_cos();
+        let (dx, dy) = (p.x - o.x, p.y - o.y);
+        let (local_x, local_y) = (dx * cos - dy * sin, dx * sin + dy * cos);
+        local_x.abs() <= o.w / 2.0 && local_y.abs() <= o.h / 2.0
+    }
+
+    pub fn oriented_rectangle_intersects_point(o: &OrientedRectangleData, p: &PointData) -> bool {
+        point_intersects_oriented_rectangle(p, o)
+    }
+
+    pub fn rectangle_intersects_polygon(r: &RectangleData, poly: &PolygonData) -> bool {
+        convex_intersects_convex(&rectangle_vertices(r), &polygon_vertices(poly))
+    }
+
+    pub fn polygon_intersects_rectangle(poly: &PolygonData, r: &RectangleData) -> bool {
+        rectangle_intersects_polygon(r, poly)
+    }
+
+    pub fn rectangle_intersects_capsule(r: &RectangleData, cap: &CapsuleData) -> bool {
+        convex_intersects_capsule(&rectangle_vertices(r), cap)
+    }
+
+    pub fn capsule_intersects_rectangle(cap: &CapsuleData, r: &RectangleData) -> bool {
+        rectangle_intersects_capsule(r, cap)
+    }
+
+    pub fn rectangle_intersects_oriented_rectangle(
+        r: &RectangleData,
+        o: &OrientedRectangleData,
+    ) -> bool {
+        convex_intersects_convex(&rectangle_vertices(r), &oriented_rectangle_vertices(o))
+    }
+
+    pub fn oriented_rectangle_intersects_rectangle(
+        o: &OrientedRectangleData,
+        r: &RectangleData,
+    ) -> bool {
+        rectangle_intersects_oriented_rectangle(r, o)
+    }
+
+    pub fn circle_intersects_polygon(c: &CircleData, poly: &PolygonData) -> bool {
+        convex_intersects_circle(&polygon_vertices(poly), c)
+    }
+
+    pub fn polygon_intersects_circle(poly: &PolygonData, c: &CircleData) -> bool {
+        circle_intersects_polygon(c, poly)
+    }
+
+    pub fn circle_intersects_capsule(c: &CircleData, cap: &CapsuleData) -> bool {
+        segment_distance_sq_to_point((cap.x1, cap.y1), (cap.x2, cap.y2), (c.x, c.y))
+            <= (cap.r + c.r).powi(2)
+    }
+
+    pub fn capsule_intersects_circle(cap: &CapsuleData, c: &CircleData) -> bool {
+        circle_intersects_capsule(c, cap)
+    }
+
+    pub fn circle_intersects_oriented_rectangle(c: &CircleData, o: &OrientedRectangleData) -> bool {
+        convex_intersects_circle(&oriented_rectangle_vertices(o), c)
+    }
+
+    pub fn oriented_rectangle_intersects_circle(o: &OrientedRectangleData, c: &CircleData) -> bool {
+        circle_intersects_oriented_rectangle(c, o)
+    }
+
+    pub fn polygon_intersects_polygon(p1: &PolygonData, p2: &PolygonData) -> bool {
+        convex_intersects_convex(&polygon_vertices(p1), &polygon_vertices(p2))
+    }
+
+    pub fn polygon_intersects_capsule(poly: &PolygonData, cap: &CapsuleData) -> bool {
+        convex_intersects_capsule(&polygon_vertices(poly), cap)
+    }
+
+    pub fn capsule_intersects_polygon(cap: &CapsuleData, poly: &PolygonData) -> bool {
+        polygon_intersects_capsule(poly, cap)
+    }
+
+    pub fn polygon_intersects_oriented_rectangle(
+        poly: &PolygonData,
+        o: &OrientedRectangleData,
+    ) -> bool {
+        convex_intersects_convex(&polygon_vertices(poly), &oriented_rectangle_vertices(o))
+    }
+
+    pub fn oriented_rectangle_intersects_polygon(
+        o: &OrientedRectangleData,
+        poly: &PolygonData,
+    ) -> bool {
+        polygon_intersects_oriented_rectangle(poly, o)
+    }
+
+    pub fn capsule_intersects_capsule(c1: &CapsuleData, c2: &CapsuleData) -> bool {
+        segment_distance_sq_to_segment(
+            ((c1.x1, c1.y1), (c1.x2, c1.y2)),
+            ((c2.x1, c2.y1), (c2.x2, c2.y2)),
+        ) <= (c1.r + c2.r).powi(2)
+    }
+
+    pub fn capsule_intersects_oriented_rectangle(
+        cap: &CapsuleData,
+        o: &OrientedRectangleData,
+    ) -> bool {
+        convex_intersects_capsule(&oriented_rectangle_vertices(o), cap)
+    }
+
+    pub fn oriented_rectangle_intersects_capsule(
+        o: &OrientedRectangleData,
+        cap: &CapsuleData,
+    ) -> bool {
+        capsule_intersects_oriented_rectangle(cap, o)
+    }
+
+    pub fn oriented_rectangle_intersects_oriented_rectangle(
+        o1: &OrientedRectangleData,
+        o2: &OrientedRectangleData,
+    ) -> bool {
+        convex_intersects_convex(
+            &oriented_rectangle_vertices(o1),
+            &oriented_rectangle_vertices(o2),
+        )
+    }
+
+    pub fn rectangle_vertices(r: &RectangleData) -> Vec<Vertex> {
+        vec![
+            (r.x, r.y),
+            (r.x + r.w, r.y),
+            (r.x + r.w, r.y + r.h),
+            (r.x, r.y + r.h),
+        ]
+    }
+
+    pub fn polygon_vertices(poly: &PolygonData) -> Vec<Vertex> {
+        poly.points.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    pub fn oriented_rectangle_vertices(o: &OrientedRectangleData) -> Vec<Vertex> {
+        let (sin, cos) = o.angle.sin_cos();
+        let (half_w, half_h) = (o.w / 2.0, o.h / 2.0);
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .iter()
+            .map(|(sx, sy)| {
+                let (lx, ly) = (sx * half_w, sy * half_h);
+                (o.x + lx * cos - ly * sin, o.y + lx * sin + ly * cos)
+            })
+            .collect()
+    }
+
+    pub fn vertices_bounding_box(vertices: &[Vertex]) -> RectangleData {
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in vertices {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        RectangleData::new(min.0, min.1, max.0 - min.0, max.1 - min.1)
+    }
+
+    fn cross(o: Vertex, a: Vertex, b: Vertex) -> f64 {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    }
+
+    /// Works for either winding order: the point is inside as long as it's on
+    /// the same side of every edge. Points on an edge count as inside.
+    pub fn convex_contains_point(vertices: &[Vertex], x: f64, y: f64) -> bool {
+        let (mut positive, mut negative) = (false, false);
+        for (i, &a) in vertices.iter().enumerate() {
+            let b = vertices[(i + 1) % vertices.len()];
+            let side = cross(a, b, (x, y));
+            if side > 0.0 {
+                positive = true;
+            } else if side < 0.0 {
+                negative = true;
+            }
+            if positive && negative {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Separating axis test: two convex shapes are disjoint iff there's an
+    /// edge normal from one of them along which their projections don't
+    /// overlap.
+    pub fn convex_intersects_convex(v1: &[Vertex], v2: &[Vertex]) -> bool {
+        for vertices in [v1, v2].iter() {
+            for (i, &a) in vertices.iter().enumerate() {
+                let b = vertices[(i + 1) % vertices.len()];
+                let axis = (a.1 - b.1, b.0 - a.0);
+                let (min1, max1) = project(v1, axis);
+                let (min2, max2) = project(v2, axis);
+                if max1 < min2 || max2 < min1 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn project(vertices: &[Vertex], axis: Vertex) -> (f64, f64) {
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for &(x, y) in vertices {
+            let dot = x * axis.0 + y * axis.1;
+            min = min.min(dot);
+            max = max.max(dot);
+        }
+        (min, max)
+    }
+
+    pub fn convex_intersects_circle(vertices: &[Vertex], c: &CircleData) -> bool {
+        if convex_contains_point(vertices, c.x, c.y) {
+            return true;
+        }
+        let r_sq = c.r.powi(2);
+        vertices.iter().enumerate().any(|(i, &a)| {
+            let b = vertices[(i + 1) % vertices.len()];
+            segment_distance_sq_to_point(a, b, (c.x, c.y)) <= r_sq
+        })
+    }
+
+    pub fn convex_intersects_capsule(vertices: &[Vertex], cap: &CapsuleData) -> bool {
+        let (a, b) = ((cap.x1, cap.y1), (cap.x2, cap.y2));
+        if convex_contains_point(vertices, a.0, a.1) {
+            return true;
+        }
+        let r_sq = cap.r.powi(2);
+        vertices.iter().enumerate().any(|(i, &v1)| {
+            let v2 = vertices[(i + 1) % vertices.len()];
+            segment_distance_sq_to_segment((a, b), (v1, v2)) <= r_sq
+        })
+    }
+
+    pub fn segment_distance_sq_to_point(a: Vertex, b: Vertex, p: Vertex) -> f64 {
+        let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+        let len_sq = abx.powi(2) + aby.powi(2);
+        let t = if len_sq == 0.0 {
+            0.0
+        } else {
+            (((p.0 - a.0) * abx + (p.1 - a.1) * aby) / len_sq).clamp(0.0, 1.0)
+        };
+        let (cx, cy) = (a.0 + abx * t, a.1 + aby * t);
+        (p.0 - cx).powi(2) + (p.1 - cy).powi(2)
+    }
+
+    pub fn segments_intersect(s1: (Vertex, Vertex), s2: (Vertex, Vertex)) -> bool {
+        let (a, b) = s1;
+        let (c, d) = s2;
+        let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+        let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+        if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+            && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+        {
+            return true;
+        }
+        // Collinear and touching cases are handled by the distance check in
+        // the caller, which will come out as zero.
+        false
+    }
+
+    pub fn segment_distance_sq_to_segment(s1: (Vertex, Vertex), s2: (Vertex, Vertex)) -> f64 {
+        if segments_intersect(s1, s2) {
+            return 0.0;
+        }
+        segment_distance_sq_to_point(s1.0, s1.1, s2.0)
+            .min(segment_distance_sq_to_point(s1.0, s1.1, s2.1))
+            .min(segment_distance_sq_to_point(s2.0, s2.1, s1.0))
+            .min(segment_distance_sq_to_point(s2.0, s2.1, s1.1))
+    }
+
+    #[cfg(test)]
+    pub fn region_primitives(region: &Region) -> Vec<Primitive> {
+        match region {
+            Region::Point(p) => vec![Primitive::Point((p.x, p.y))],
+            Region::Rectangle(r) => rectangle_vertices(r)
+                .into_iter()
+                .map(Primitive::Point)
+                .collect(),
+            Region::Circle(c) => vec![Primitive::Circle((c.x, c.y), c.r)],
+            Region::Polygon(poly) => polygon_vertices(poly)
+                .into_iter()
+                .map(Primitive::Point)
+                .collect(),
+            Region::Capsule(cap) => vec![
+                Primitive::Circle((cap.x1, cap.y1), cap.r),
+                Primitive::Circle((cap.x2, cap.y2), cap.r),
+            ],
+            Region::OrientedRectangle(o) => oriented_rectangle_vertices(o)
+                .into_iter()
+                .map(Primitive::Point)
+                .collect(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn region_contains_primitive(region: &Region, primitive: &Primitive) -> bool {
+        match *primitive {
+            Primitive::Point((x, y)) => region.intersects(&PointData::new(x, y)),
+            Primitive::Circle(center, r) => region_contains_circle(region, center, r),
+        }
+    }
+
+    #[cfg(test)]
+    fn region_contains_circle(region: &Region, (x, y): Vertex, r: f64) -> bool {
+        match region {
+            Region::Point(p) => approx_eq(r, 0.0) && approx_eq(p.x, x) && approx_eq(p.y, y),
+            Region::Rectangle(rect) => {
+                x - r >= rect.x
+                    && y - r >= rect.y
+                    && x + r <= rect.x + rect.w
+                    && y + r <= rect.y + rect.h
+            }
+            Region::Circle(c) => ((x - c.x).powi(2) + (y - c.y).powi(2)).sqrt() + r <= c.r,
+            Region::Polygon(poly) => convex_contains_circle(&polygon_vertices(poly), (x, y), r),
+            Region::Capsule(cap) => {
+                segment_distance_sq_to_point((cap.x1, cap.y1), (cap.x2, cap.y2), (x, y)).sqrt() + r
+                    <= cap.r
+            }
+            Region::OrientedRectangle(o) => {
+                convex_contains_circle(&oriented_rectangle_vertices(o), (x, y), r)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn convex_contains_circle(vertices: &[Vertex], center: Vertex, r: f64) -> bool {
+        if !convex_contains_point(vertices, center.0, center.1) {
+            return false;
+        }
+        let r_sq = r.powi(2);
+        vertices.iter().enumerate().all(|(i, &a)| {
+            let b = vertices[(i + 1) % vertices.len()];
+            segment_distance_sq_to_point(a, b, center) >= r_sq
+        })
+    }
 }
 
 #[cfg(test)]
@@ -342,7 +1170,7 @@ mod tests {
         let (elem1, elem2) = (Place::point(0, 2.0, 4.0), Place::point(1, 3.0, 6.0));
         qt.insert(&elem1);
         qt.insert(&elem2);
-        assert_eq!(qt.contents.len(), 2);
+        assert_eq!(qt.root.contents.len(), 2);
     }
 
     #[test]
@@ -381,12 +1209,12 @@ mod tests {
             let val = i as f64;
             values.push(Place::point(i, 3.0 * val, 6.0 * val));
         }
-        for i in 0..15 {
-            qt.insert(&values[i]);
+        for value in values.iter() {
+            qt.insert(value);
         }
         println!("Split Quadtree: {:?}", qt);
         // If we split then we've distributed all the elements to the children.
-        assert_eq!(qt.contents.len(), 0);
+        assert_eq!(qt.root.contents.len(), 0);
     }
 
     #[test]
@@ -518,4 +1346,427 @@ mod tests {
         assert!(math::rectangle_intersects_circle(&control, &outside));
         assert!(math::circle_intersects_rectangle(&outside, &control));
     }
+
+    #[test]
+    fn test_polygon_intersects_point() {
+        let triangle = PolygonData::new(vec![
+            PointData::new(0.0, 0.0),
+            PointData::new(10.0, 0.0),
+            PointData::new(0.0, 10.0),
+        ])
+        .unwrap();
+        let inside = PointData::new(2.0, 2.0);
+        assert!(math::polygon_intersects_point(&triangle, &inside));
+        assert!(math::point_intersects_polygon(&inside, &triangle));
+        let on_edge = PointData::new(5.0, 5.0);
+        assert!(math::polygon_intersects_point(&triangle, &on_edge));
+        let outside = PointData::new(6.0, 6.0);
+        assert!(!math::polygon_intersects_point(&triangle, &outside));
+        assert!(!math::point_intersects_polygon(&outside, &triangle));
+        // Winding order shouldn't matter.
+        let reversed = PolygonData::new(vec![
+            PointData::new(0.0, 10.0),
+            PointData::new(10.0, 0.0),
+            PointData::new(0.0, 0.0),
+        ])
+        .unwrap();
+        assert!(reversed.contains_point(2.0, 2.0));
+        assert!(!reversed.contains_point(6.0, 6.0));
+        // Too few points to enclose anything.
+        assert!(Region::new_polygon(&[(0.0, 0.0), (10.0, 0.0)]).is_none());
+    }
+
+    #[test]
+    fn test_polygon_intersects_polygon() {
+        let square = PolygonData::new(vec![
+            PointData::new(0.0, 0.0),
+            PointData::new(4.0, 0.0),
+            PointData::new(4.0, 4.0),
+            PointData::new(0.0, 4.0),
+        ])
+        .unwrap();
+        let diamond = PolygonData::new(vec![
+            PointData::new(6.0, 2.0),
+            PointData::new(8.0, 4.0),
+            PointData::new(6.0, 6.0),
+            PointData::new(3.5, 4.0),
+        ])
+        .unwrap();
+        assert!(math::polygon_intersects_polygon(&square, &diamond));
+        assert!(math::polygon_intersects_polygon(&diamond, &square));
+        // Bounding boxes overlap, but the shapes themselves don't.
+        let far_diamond = PolygonData::new(vec![
+            PointData::new(6.0, 2.0),
+            PointData::new(8.0, 4.0),
+            PointData::new(6.0, 6.0),
+            PointData::new(4.5, 4.0),
+        ])
+        .unwrap();
+        assert!(!math::polygon_intersects_polygon(&square, &far_diamond));
+        assert!(!math::polygon_intersects_polygon(&far_diamond, &square));
+    }
+
+    #[test]
+    fn test_polygon_intersects_rectangle_and_circle() {
+        let triangle = PolygonData::new(vec![
+            PointData::new(0.0, 0.0),
+            PointData::new(10.0, 0.0),
+            PointData::new(0.0, 10.0),
+        ])
+        .unwrap();
+        let overlap = RectangleData::new(4.0, 4.0, 3.0, 3.0);
+        assert!(math::polygon_intersects_rectangle(&triangle, &overlap));
+        assert!(math::rectangle_intersects_polygon(&overlap, &triangle));
+        let corner = RectangleData::new(6.0, 6.0, 3.0, 3.0);
+        assert!(!math::polygon_intersects_rectangle(&triangle, &corner));
+        assert!(!math::rectangle_intersects_polygon(&corner, &triangle));
+        let touching = CircleData::new(6.0, 6.0, 1.5);
+        assert!(math::polygon_intersects_circle(&triangle, &touching));
+        assert!(math::circle_intersects_polygon(&touching, &triangle));
+        let apart = CircleData::new(7.0, 7.0, 1.0);
+        assert!(!math::polygon_intersects_circle(&triangle, &apart));
+        assert!(!math::circle_intersects_polygon(&apart, &triangle));
+        let containing = CircleData::new(3.0, 3.0, 50.0);
+        assert!(math::polygon_intersects_circle(&triangle, &containing));
+    }
+
+    #[test]
+    fn test_capsule_intersects_point_and_circle() {
+        let cap = CapsuleData::new(0.0, 0.0, 10.0, 0.0, 2.0);
+        let middle = PointData::new(5.0, 1.5);
+        assert!(math::capsule_intersects_point(&cap, &middle));
+        assert!(math::point_intersects_capsule(&middle, &cap));
+        let past_end = PointData::new(11.5, 1.5);
+        assert!(!math::capsule_intersects_point(&cap, &past_end));
+        assert!(cap.contains_point(11.0, 0.0));
+        let near = CircleData::new(5.0, 4.0, 2.1);
+        assert!(math::capsule_intersects_circle(&cap, &near));
+        assert!(math::circle_intersects_capsule(&near, &cap));
+        let far = CircleData::new(5.0, 5.0, 2.9);
+        assert!(!math::capsule_intersects_circle(&cap, &far));
+        assert!(!math::circle_intersects_capsule(&far, &cap));
+    }
+
+    #[test]
+    fn test_capsule_intersects_capsule() {
+        let horizontal = CapsuleData::new(0.0, 0.0, 10.0, 0.0, 1.0);
+        let crossing = CapsuleData::new(5.0, -5.0, 5.0, 5.0, 0.1);
+        assert!(math::capsule_intersects_capsule(&horizontal, &crossing));
+        assert!(math::capsule_intersects_capsule(&crossing, &horizontal));
+        let parallel = CapsuleData::new(0.0, 1.9, 10.0, 1.9, 1.0);
+        assert!(math::capsule_intersects_capsule(&horizontal, &parallel));
+        let apart = CapsuleData::new(0.0, 3.0, 10.0, 3.0, 0.5);
+        assert!(!math::capsule_intersects_capsule(&horizontal, &apart));
+        assert!(!math::capsule_intersects_capsule(&apart, &horizontal));
+    }
+
+    #[test]
+    fn test_capsule_intersects_shapes() {
+        let rect = RectangleData::new(0.0, 0.0, 4.0, 4.0);
+        let through = CapsuleData::new(-5.0, 2.0, 10.0, 2.0, 0.1);
+        assert!(math::capsule_intersects_rectangle(&through, &rect));
+        assert!(math::rectangle_intersects_capsule(&rect, &through));
+        let grazing = CapsuleData::new(-5.0, 5.0, 10.0, 5.0, 1.0);
+        assert!(math::capsule_intersects_rectangle(&grazing, &rect));
+        let apart = CapsuleData::new(-5.0, 6.0, 10.0, 6.0, 1.0);
+        assert!(!math::capsule_intersects_rectangle(&apart, &rect));
+        assert!(!math::rectangle_intersects_capsule(&rect, &apart));
+        let inside = CapsuleData::new(1.0, 1.0, 2.0, 2.0, 0.5);
+        assert!(math::capsule_intersects_rectangle(&inside, &rect));
+        let triangle = PolygonData::new(vec![
+            PointData::new(0.0, 0.0),
+            PointData::new(10.0, 0.0),
+            PointData::new(0.0, 10.0),
+        ])
+        .unwrap();
+        let diagonal = CapsuleData::new(6.0, 6.0, 9.0, 9.0, 1.0);
+        assert!(!math::capsule_intersects_polygon(&diagonal, &triangle));
+        assert!(!math::polygon_intersects_capsule(&triangle, &diagonal));
+        let fat = CapsuleData::new(6.0, 6.0, 9.0, 9.0, 1.5);
+        assert!(math::capsule_intersects_polygon(&fat, &triangle));
+        let rotated = OrientedRectangleData::new(0.0, 0.0, 4.0, 2.0, std::f64::consts::FRAC_PI_2);
+        let vertical = CapsuleData::new(1.5, -5.0, 1.5, 5.0, 0.1);
+        assert!(!math::capsule_intersects_oriented_rectangle(
+            &vertical, &rotated
+        ));
+        let nearer = CapsuleData::new(0.5, -5.0, 0.5, 5.0, 0.1);
+        assert!(math::capsule_intersects_oriented_rectangle(
+            &nearer, &rotated
+        ));
+        assert!(math::oriented_rectangle_intersects_capsule(
+            &rotated, &nearer
+        ));
+    }
+
+    #[test]
+    fn test_oriented_rectangle_intersects_point() {
+        let diamond = OrientedRectangleData::new(0.0, 0.0, 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        let tip = PointData::new(1.4, 0.0);
+        assert!(math::oriented_rectangle_intersects_point(&diamond, &tip));
+        assert!(math::point_intersects_oriented_rectangle(&tip, &diamond));
+        let corner = PointData::new(0.9, 0.9);
+        assert!(!math::oriented_rectangle_intersects_point(
+            &diamond, &corner
+        ));
+        assert!(!math::point_intersects_oriented_rectangle(
+            &corner, &diamond
+        ));
+    }
+
+    #[test]
+    fn test_oriented_rectangle_intersects_shapes() {
+        let diamond = OrientedRectangleData::new(0.0, 0.0, 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        let corner_rect = RectangleData::new(0.8, 0.8, 1.0, 1.0);
+        assert!(!math::oriented_rectangle_intersects_rectangle(
+            &diamond,
+            &corner_rect
+        ));
+        assert!(!math::rectangle_intersects_oriented_rectangle(
+            &corner_rect,
+            &diamond
+        ));
+        let side_rect = RectangleData::new(1.3, -0.5, 1.0, 1.0);
+        assert!(math::oriented_rectangle_intersects_rectangle(
+            &diamond, &side_rect
+        ));
+        assert!(math::rectangle_intersects_oriented_rectangle(
+            &side_rect, &diamond
+        ));
+        let corner_circle = CircleData::new(1.0, 1.0, 0.4);
+        assert!(!math::oriented_rectangle_intersects_circle(
+            &diamond,
+            &corner_circle
+        ));
+        assert!(!math::circle_intersects_oriented_rectangle(
+            &corner_circle,
+            &diamond
+        ));
+        let tip_circle = CircleData::new(2.0, 0.0, 0.6);
+        assert!(math::oriented_rectangle_intersects_circle(
+            &diamond,
+            &tip_circle
+        ));
+        let other = OrientedRectangleData::new(2.5, 0.0, 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        assert!(math::oriented_rectangle_intersects_oriented_rectangle(
+            &diamond, &other
+        ));
+        let apart = OrientedRectangleData::new(3.0, 0.0, 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        assert!(!math::oriented_rectangle_intersects_oriented_rectangle(
+            &diamond, &apart
+        ));
+        let triangle = PolygonData::new(vec![
+            PointData::new(1.0, 1.0),
+            PointData::new(3.0, 1.0),
+            PointData::new(1.0, 3.0),
+        ])
+        .unwrap();
+        assert!(!math::oriented_rectangle_intersects_polygon(
+            &diamond, &triangle
+        ));
+        assert!(!math::polygon_intersects_oriented_rectangle(
+            &triangle, &diamond
+        ));
+    }
+
+    #[test]
+    fn test_new_shape_bounding_boxes() {
+        let triangle = PolygonData::new(vec![
+            PointData::new(1.0, 2.0),
+            PointData::new(5.0, -1.0),
+            PointData::new(3.0, 7.0),
+        ])
+        .unwrap();
+        let bb = triangle.bounding_box();
+        assert!(math::approx_eq(bb.x, 1.0) && math::approx_eq(bb.y, -1.0));
+        assert!(math::approx_eq(bb.w, 4.0) && math::approx_eq(bb.h, 8.0));
+        let cap = CapsuleData::new(4.0, 0.0, 0.0, 2.0, 1.0);
+        let bb = cap.bounding_box();
+        assert!(math::approx_eq(bb.x, -1.0) && math::approx_eq(bb.y, -1.0));
+        assert!(math::approx_eq(bb.w, 6.0) && math::approx_eq(bb.h, 4.0));
+        let diamond = OrientedRectangleData::new(0.0, 0.0, 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        let bb = diamond.bounding_box();
+        let half_diagonal = 2f64.sqrt();
+        assert!(math::approx_eq(bb.x, -half_diagonal) && math::approx_eq(bb.y, -half_diagonal));
+        assert!(math::approx_eq(bb.w, 2.0 * half_diagonal));
+    }
+
+    #[test]
+    fn test_query_with_new_shapes() {
+        let mut qt = QuadTree::new(RectangleData::new(0.0, 0.0, 100.0, 100.0));
+        let p1 = Place::point(0, 10.0, 10.0);
+        let p2 = Place::point(1, 50.0, 52.0);
+        let p3 = Place::point(2, 90.0, 10.0);
+        qt.insert(&p1);
+        qt.insert(&p2);
+        qt.insert(&p3);
+        let beam = Region::new_capsule(0.0, 0.0, 60.0, 60.0, 3.0);
+        let results = qt.query(&beam);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.index != 2));
+        let zone = Region::new_polygon(&[(80.0, 0.0), (100.0, 0.0), (100.0, 20.0)]).unwrap();
+        let results = qt.query(&zone);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].index, 2);
+        let selection = Region::new_oriented_rect(50.0, 50.0, 10.0, 2.0, 0.0);
+        assert!(qt.query(&selection).is_empty());
+        let selection =
+            Region::new_oriented_rect(50.0, 50.0, 10.0, 2.0, std::f64::consts::FRAC_PI_2);
+        assert_eq!(qt.query(&selection).len(), 1);
+    }
+
+    #[test]
+    fn test_region_contains() {
+        let rect = Region::new_rect(0.0, 0.0, 10.0, 10.0);
+        assert!(rect.contains(&Region::new_rect(2.0, 2.0, 3.0, 3.0)));
+        assert!(!rect.contains(&Region::new_rect(8.0, 8.0, 3.0, 3.0)));
+        assert!(rect.contains(&Region::new_circle(5.0, 5.0, 5.0)));
+        assert!(!rect.contains(&Region::new_circle(5.0, 5.0, 5.1)));
+        assert!(rect.contains(&Region::new_point(10.0, 0.0)));
+        assert!(rect.contains(&Region::new_capsule(2.0, 2.0, 8.0, 8.0, 1.0)));
+        assert!(!rect.contains(&Region::new_capsule(2.0, 2.0, 8.0, 9.5, 1.0)));
+        // Intersecting isn't the same as containing.
+        let overlapping = Region::new_circle(10.0, 10.0, 3.0);
+        assert!(rect.intersects(&overlapping));
+        assert!(!rect.contains(&overlapping));
+
+        let circle = Region::new_circle(0.0, 0.0, 5.0);
+        assert!(circle.contains(&Region::new_rect(-3.0, -3.0, 6.0, 6.0)));
+        assert!(!circle.contains(&Region::new_rect(-4.0, -4.0, 8.0, 8.0)));
+        assert!(circle.contains(&Region::new_circle(1.0, 1.0, 3.5)));
+        assert!(!circle.contains(&Region::new_circle(1.0, 1.0, 4.0)));
+
+        let triangle = Region::new_polygon(&[(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]).unwrap();
+        assert!(triangle.contains(&Region::new_circle(2.0, 2.0, 1.5)));
+        assert!(!triangle.contains(&Region::new_circle(4.0, 4.0, 1.5)));
+        assert!(
+            triangle.contains(&Region::new_polygon(&[(1.0, 1.0), (4.0, 1.0), (1.0, 4.0)]).unwrap())
+        );
+        assert!(!Region::new_polygon(&[(1.0, 1.0), (4.0, 1.0), (1.0, 4.0)])
+            .unwrap()
+            .contains(&triangle));
+
+        let cap = Region::new_capsule(0.0, 0.0, 10.0, 0.0, 2.0);
+        assert!(cap.contains(&Region::new_circle(10.0, 0.5, 1.5)));
+        assert!(!cap.contains(&Region::new_circle(10.0, 0.5, 1.6)));
+        assert!(cap.contains(&Region::new_oriented_rect(5.0, 0.0, 10.0, 2.0, 0.0)));
+        assert!(!cap.contains(&Region::new_oriented_rect(5.0, 0.0, 10.0, 2.0, 0.3)));
+
+        let point = Region::new_point(1.0, 1.0);
+        assert!(point.contains(&Region::new_point(1.0, 1.0)));
+        assert!(!point.contains(&Region::new_circle(1.0, 1.0, 0.5)));
+    }
+
+    #[test]
+    fn test_region_geometry() {
+        let mut rect = Region::new_rect(1.0, 2.0, 4.0, 6.0);
+        assert!(math::approx_eq(rect.area(), 24.0));
+        let (cx, cy) = rect.center();
+        assert!(math::approx_eq(cx, 3.0) && math::approx_eq(cy, 5.0));
+        rect.translate(-1.0, 1.0);
+        let bb = rect.bounding_box();
+        assert!(math::approx_eq(bb.x(), 0.0) && math::approx_eq(bb.y(), 3.0));
+        assert!(math::approx_eq(bb.w(), 4.0) && math::approx_eq(bb.h(), 6.0));
+
+        let circle = Region::new_circle(0.0, 0.0, 2.0);
+        assert!(math::approx_eq(circle.area(), std::f64::consts::PI * 4.0));
+
+        let mut triangle = Region::new_polygon(&[(0.0, 0.0), (6.0, 0.0), (0.0, 3.0)]).unwrap();
+        assert!(math::approx_eq(triangle.area(), 9.0));
+        let (cx, cy) = triangle.center();
+        assert!(math::approx_eq(cx, 2.0) && math::approx_eq(cy, 1.0));
+        triangle.translate(2.0, 2.0);
+        assert!(triangle.contains(&Region::new_point(3.0, 3.0)));
+        assert!(!triangle.contains(&Region::new_point(1.0, 1.0)));
+
+        let cap = Region::new_capsule(0.0, 0.0, 4.0, 0.0, 1.0);
+        assert!(math::approx_eq(cap.area(), 8.0 + std::f64::consts::PI));
+        let (cx, cy) = cap.center();
+        assert!(math::approx_eq(cx, 2.0) && math::approx_eq(cy, 0.0));
+
+        let mut oriented = Region::new_oriented_rect(0.0, 0.0, 2.0, 3.0, 1.0);
+        assert!(math::approx_eq(oriented.area(), 6.0));
+        oriented.translate(5.0, -5.0);
+        let (cx, cy) = oriented.center();
+        assert!(math::approx_eq(cx, 5.0) && math::approx_eq(cy, -5.0));
+    }
+
+    #[test]
+    fn test_query_recording() {
+        let mut qt = QuadTree::new(RectangleData::new(0.0, 0.0, 100.0, 100.0));
+        let values: Vec<Place> = (0..15)
+            .map(|i| Place::point(i, i as f64 * 6.0, i as f64 * 3.0))
+            .collect();
+        for value in values.iter() {
+            qt.insert(value);
+        }
+        // Nothing gets recorded until it's turned on.
+        qt.query(&Region::new_point(1.0, 1.0));
+        assert!(qt.take_queries().is_empty());
+        qt.record_queries();
+        qt.query(&Region::new_point(1.0, 1.0));
+        qt.query(&Region::new_circle(50.0, 50.0, 10.0));
+        let queries = qt.take_queries();
+        assert_eq!(queries.len(), 2);
+        assert!(qt.take_queries().is_empty());
+
+        let leaves = qt.leaves();
+        assert_eq!(leaves.len(), 4);
+        let total_area: f64 = leaves.iter().map(|(bounds, _)| bounds.area()).sum();
+        assert!(math::approx_eq(total_area, 100.0 * 100.0));
+        let total_items: usize = leaves.iter().map(|(_, count)| count).sum();
+        assert_eq!(total_items, 15);
+    }
+
+    #[test]
+    fn test_owned_items() {
+        // Items don't need to be Copy, or even Clone, to be stored.
+        #[derive(Debug)]
+        struct Zone {
+            name: String,
+            region: Region,
+        }
+
+        impl HasRegion for Zone {
+            fn get_region(&self) -> Region {
+                self.region.clone()
+            }
+        }
+
+        let mut qt = QuadTree::new(RectangleData::new(0.0, 0.0, 100.0, 100.0));
+        assert!(qt.is_empty());
+        let pond = qt.insert(Zone {
+            name: "pond".into(),
+            region: Region::new_circle(20.0, 20.0, 5.0),
+        });
+        let field = qt.insert(Zone {
+            name: "field".into(),
+            region: Region::new_polygon(&[(50.0, 50.0), (90.0, 50.0), (70.0, 90.0)]).unwrap(),
+        });
+        assert_eq!(qt.len(), 2);
+        assert_eq!(qt.get(pond).unwrap().name, "pond");
+        assert_eq!(qt.get(field).unwrap().name, "field");
+        let results = qt.query(&Region::new_point(70.0, 60.0));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "field");
+        assert_eq!(qt.query_handles(&Region::new_point(20.0, 20.0)), vec![pond]);
+    }
+
+    #[test]
+    fn test_straddling_items_found_once() {
+        let mut qt = QuadTree::new(RectangleData::new(0.0, 0.0, 100.0, 100.0));
+        for i in 0..15 {
+            qt.insert(Place::point(i, 10.0 + i as f64, 10.0));
+        }
+        // Sits right on top of the point where all four children meet.
+        qt.insert(Place::circle(100, 50.0, 50.0, 10.0));
+        assert!(qt.leaves().len() > 1);
+        let results = qt.query(&Region::new_rect(0.0, 0.0, 100.0, 100.0));
+        assert_eq!(results.len(), 16);
+        assert_eq!(results.iter().filter(|r| r.index == 100).count(), 1);
+        // Results come back in insertion order.
+        let indices: Vec<usize> = results.iter().map(|r| r.index).collect();
+        let mut sorted = indices.clone();
+        sorted.sort();
+        assert_eq!(indices, sorted);
+    }
 }
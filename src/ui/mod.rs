@@ -5,19 +5,50 @@ use graphics::*;
 pub mod text;
 pub use text::*;
 
-pub struct GUI<'a, C: CharacterCache> {
+/// Something the player can do to the selected actor from the buttons above
+/// its panel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PanelAction {
+    Upgrade,
+    Sell,
+    /// Switch a tower over to its next way of picking targets.
+    ChangeTargeting,
+}
+
+/// What a click on the screen landed on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Click {
+    /// Nothing in the UI, so it's for the world underneath.
+    Missed,
+    /// Some part of the UI that has already dealt with it.
+    Handled,
+    /// One of the selected actor's buttons.
+    Action(PanelAction),
+}
+
+pub struct Gui<'a, C: CharacterCache> {
     paused_box: TextBox<C>,
     text_boxes: Vec<TextBox<C>>,
+    /// Game-wide status, shown in the top left when there's anything to say.
+    hud_box: Option<TextBox<C>>,
+    /// A big message in the middle of the screen, like the paused one.
+    banner_box: Option<TextBox<C>>,
+    banner_text: Option<String>,
+    /// A button above the selected panel for each thing that can be done to
+    /// the selected actor.
+    action_boxes: Vec<(PanelAction, TextBox<C>)>,
+    action_labels: Vec<(PanelAction, String)>,
     // mouse_coords: TextBox<C>,
     // hovered_actor: TextBox<C>,
     // selected_actor: TextBox<C>,
-    // screen_width: f64,
-    // screen_height: f64,
+    screen_width: f64,
+    screen_height: f64,
     glyph_cache: &'a mut C,
 }
 
-impl<'a, C: CharacterCache> GUI<'a, C> {
+impl<'a, C: CharacterCache> Gui<'a, C> {
     pub const FONT_SIZE: FontSize = 12;
+    const BANNER_SIZE: FontSize = 50;
     const MOUSE_BOX: usize = 0;
     const HOVERED_BOX: usize = 1;
     const SELECTED_BOX: usize = 2;
@@ -26,7 +57,7 @@ impl<'a, C: CharacterCache> GUI<'a, C> {
         let mut paused_box = TextBox::new(
             "GAME PAUSED",
             1000.0,
-            50,
+            Self::BANNER_SIZE,
             width / 2.0,
             height / 2.0,
             AnchorPoint::Center,
@@ -69,8 +100,13 @@ impl<'a, C: CharacterCache> GUI<'a, C> {
         Self {
             paused_box,
             text_boxes: boxes,
-            // screen_width: width,
-            // screen_height: height,
+            hud_box: None,
+            banner_box: None,
+            banner_text: None,
+            action_boxes: vec![],
+            action_labels: vec![],
+            screen_width: width,
+            screen_height: height,
             glyph_cache,
         }
     }
@@ -79,70 +115,170 @@ impl<'a, C: CharacterCache> GUI<'a, C> {
     where
         G: Graphics<Texture = <C as character::CharacterCache>::Texture>,
     {
-        for text_box in self.text_boxes.iter() {
-            text_box.render(&mut self.glyph_cache, c, g)?;
+        let action_boxes = self.action_boxes.iter().map(|(_, text_box)| text_box);
+        for text_box in self
+            .text_boxes
+            .iter()
+            .chain(self.hud_box.iter())
+            .chain(action_boxes)
+        {
+            text_box.render(self.glyph_cache, c, g)?;
         }
         if paused {
-            self.paused_box.render(&mut self.glyph_cache, c, g)?;
+            self.paused_box.render(self.glyph_cache, c, g)?;
+        } else if let Some(banner_box) = &self.banner_box {
+            banner_box.render(self.glyph_cache, c, g)?;
         }
         Ok(())
     }
 
     pub fn resize(&mut self, w: f64, h: f64) {
+        self.screen_width = w;
+        self.screen_height = h;
+        if let Some(banner_box) = &mut self.banner_box {
+            banner_box.reposition(w / 2.0, h / 2.0, AnchorPoint::Center);
+        }
         self.text_boxes[Self::MOUSE_BOX].reposition(w, 0.0, AnchorPoint::TopRight);
         self.text_boxes[Self::HOVERED_BOX].set_width(w / 2.0, self.glyph_cache);
         self.text_boxes[Self::HOVERED_BOX].reposition(w, h, AnchorPoint::BottomRight);
         self.text_boxes[Self::SELECTED_BOX].set_width(w / 2.0, self.glyph_cache);
         self.text_boxes[Self::SELECTED_BOX].reposition(0.0, h, AnchorPoint::BottomLeft);
+        self.lay_out_actions();
     }
 
     pub fn mouse_pos(&mut self, x: f64, y: f64) {
         self.text_boxes[Self::MOUSE_BOX].update_text_one_line(
             format!("({}, {})", x.floor(), y.floor()).as_str(),
-            &mut self.glyph_cache,
+            self.glyph_cache,
         );
     }
 
+    /// Show `text` in the top left, or nothing if it's empty.
+    pub fn hud(&mut self, text: &str) {
+        if text.is_empty() {
+            self.hud_box = None;
+            return;
+        }
+        match &mut self.hud_box {
+            Some(hud_box) => hud_box.update_text(text, self.glyph_cache),
+            None => {
+                self.hud_box = Some(TextBox::new(
+                    text,
+                    300.0,
+                    Self::FONT_SIZE,
+                    0.0,
+                    0.0,
+                    AnchorPoint::TopLeft,
+                    self.glyph_cache,
+                ))
+            }
+        }
+    }
+
+    /// Show `text` across the middle of the screen, or take it down if it's
+    /// `None`.
+    pub fn banner(&mut self, text: Option<&str>) {
+        if self.banner_text.as_deref() == text {
+            return;
+        }
+        self.banner_text = text.map(String::from);
+        self.banner_box = text.map(|text| {
+            let mut banner_box = TextBox::new(
+                text,
+                1000.0,
+                Self::BANNER_SIZE,
+                self.screen_width / 2.0,
+                self.screen_height / 2.0,
+                AnchorPoint::Center,
+                self.glyph_cache,
+            );
+            banner_box.auto_width(self.glyph_cache);
+            banner_box.realign();
+            banner_box
+        });
+    }
+
     pub fn hovered_desc(&mut self, desc: &str) {
-        if desc == "" {
+        if desc.is_empty() {
             self.text_boxes[Self::HOVERED_BOX]
-                .update_text("Hover over a unit for details.", &mut self.glyph_cache);
+                .update_text("Hover over a unit for details.", self.glyph_cache);
             self.text_boxes[Self::HOVERED_BOX].reset_scroll();
         } else {
-            self.text_boxes[Self::HOVERED_BOX].update_text(desc, &mut self.glyph_cache);
+            self.text_boxes[Self::HOVERED_BOX].update_text(desc, self.glyph_cache);
         }
     }
 
     pub fn selected_desc(&mut self, desc: &str) {
-        if desc == "" {
-            self.text_boxes[Self::SELECTED_BOX].update_text(
-                "Click on a unit to pin details here.",
-                &mut self.glyph_cache,
-            );
+        if desc.is_empty() {
+            self.text_boxes[Self::SELECTED_BOX]
+                .update_text("Click on a unit to pin details here.", self.glyph_cache);
             self.text_boxes[Self::SELECTED_BOX].reset_scroll();
         } else {
-            self.text_boxes[Self::SELECTED_BOX].update_text(desc, &mut self.glyph_cache);
+            self.text_boxes[Self::SELECTED_BOX].update_text(desc, self.glyph_cache);
         }
     }
 
-    /// Returns whether the click was on a UI element. If true, the click was
-    /// already handled.
-    pub fn handle_click(&mut self, x: f64, y: f64) -> bool {
-        if let Some(_text_box) = self.in_bounds(x, y) {
-            // TODO: Actually handle the click here.
-            true
+    /// Show a button for each of `actions` above the selected panel, with
+    /// the given labels.
+    pub fn selected_actions(&mut self, actions: &[(PanelAction, String)]) {
+        if self.action_labels.as_slice() == actions {
+            return;
+        }
+        self.action_labels = actions.to_vec();
+        let glyph_cache = &mut *self.glyph_cache;
+        self.action_boxes = actions
+            .iter()
+            .map(|(action, label)| {
+                let mut text_box = TextBox::new(
+                    label,
+                    1000.0,
+                    Self::FONT_SIZE,
+                    0.0,
+                    0.0,
+                    AnchorPoint::BottomLeft,
+                    glyph_cache,
+                );
+                text_box.auto_width(glyph_cache);
+                (*action, text_box)
+            })
+            .collect();
+        self.lay_out_actions();
+    }
+
+    /// Line the action buttons up along the top of the selected panel.
+    fn lay_out_actions(&mut self) {
+        let top = self.text_boxes[Self::SELECTED_BOX].top();
+        let mut x = 0.0;
+        for (_, text_box) in self.action_boxes.iter_mut() {
+            text_box.reposition(x, top, AnchorPoint::BottomLeft);
+            x += text_box.width() + TextBox::<C>::MARGIN;
+        }
+    }
+
+    /// Work out what a click at (x, y) was on, dealing with it if it was
+    /// part of the UI that looks after itself.
+    pub fn handle_click(&mut self, x: f64, y: f64) -> Click {
+        let action = self
+            .action_boxes
+            .iter()
+            .find(|(_, text_box)| text_box.in_bounds(x, y))
+            .map(|(action, _)| *action);
+        if let Some(action) = action {
+            Click::Action(action)
+        } else if self.in_bounds(x, y).is_some() {
+            // The text boxes don't do anything when clicked, but shouldn't
+            // let the click fall through to the world behind them either.
+            Click::Handled
         } else {
-            false
+            Click::Missed
         }
     }
 
     fn in_bounds(&mut self, x: f64, y: f64) -> Option<&mut TextBox<C>> {
-        for text_box in self.text_boxes.iter_mut() {
-            if text_box.in_bounds(x, y) {
-                return Some(text_box);
-            }
-        }
-        None
+        self.text_boxes
+            .iter_mut()
+            .chain(self.hud_box.iter_mut())
+            .find(|text_box| text_box.in_bounds(x, y))
     }
 
     pub fn handle_scroll(&mut self, x: f64, y: f64, up: bool) -> bool {
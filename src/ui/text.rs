@@ -6,7 +6,7 @@ use std::marker::PhantomData;
 
 #[derive(Clone, Copy)]
 pub enum AnchorPoint {
-    // TopLeft,
+    TopLeft,
     TopRight,
     BottomLeft,
     BottomRight,
@@ -124,9 +124,14 @@ impl<C: CharacterCache> TextBox<C> {
         }
     }
 
-    // pub fn width(&self) -> f64 {
-    //     self.width
-    // }
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Where the top edge of the box is on screen.
+    pub fn top(&self) -> f64 {
+        self.abs_y
+    }
 
     pub fn auto_width(&mut self, glyph_cache: &mut C) {
         let mut final_width = 0.0;
@@ -175,7 +180,7 @@ impl<C: CharacterCache> TextBox<C> {
     pub fn realign(&mut self) {
         use AnchorPoint::*;
         let (x, y) = match self.anchor {
-            // TopLeft => (self.x, self.y),
+            TopLeft => (self.x, self.y),
             TopRight => (self.x - self.width, self.y),
             BottomLeft => (self.x, self.y - self.height()),
             BottomRight => (self.x - self.width, self.y - self.height()),
@@ -252,7 +257,7 @@ fn wrap<C: CharacterCache>(
         let add_space = cur_length > 0.0;
         cur_length += word_length + if add_space { space_width } else { 0.0 };
         if add_space {
-            cur_line.push_str(" ");
+            cur_line.push(' ');
         }
         cur_line.push_str(word);
     }
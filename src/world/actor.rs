@@ -1,9 +1,12 @@
 use graphics::math::Matrix2d;
+use graphics::types::Color;
 use graphics::*;
+use std::collections::VecDeque;
 use std::time::Instant;
 
+use crate::world::script::Scripted;
 use crate::world::*;
-use map::WorldBounds;
+use map::{Map, WorldBounds, GRID_TILE_SIZE};
 
 #[derive(Debug)]
 pub struct Actor {
@@ -12,9 +15,30 @@ pub struct Actor {
     pub y: f64,
     pub speed: Option<f64>,
     sight: Option<f64>,
+    /// Which way the actor is facing, in radians clockwise from the x axis.
+    pub heading: f64,
+    /// How wide the actor's view is, in radians.
+    pub fov: f64,
     body: ActorBody,
     pub ai: ActorAi,
-    pub task: Option<Task>,
+    pub tasks: VecDeque<Task>,
+    pub perception: Perception,
+    /// How much the actor is carrying, up to `MAX_LOAD`.
+    pub load: f64,
+    /// Which side this actor is on.
+    pub faction: Faction,
+    pub hp: f64,
+    pub max_hp: f64,
+    /// How this actor died, once it has.
+    pub death: Option<Death>,
+    pub statuses: Statuses,
+    /// What the player gets paid for killing this actor.
+    pub bounty: u32,
+    /// How many others this actor has killed.
+    pub kills: u32,
+    /// The top left cell of the ground it's built over, for anything that
+    /// sits on the grid.
+    pub corner: Option<(usize, usize)>,
     pub id: Instant,
 }
 
@@ -25,10 +49,22 @@ impl Actor {
             x,
             y,
             speed: Some(20.0),
-            sight: Some(25.0),
+            sight: body.sight(),
+            heading: 0.0,
+            fov: body.fov(),
             body,
             ai,
-            task: None,
+            tasks: VecDeque::new(),
+            perception: Perception::default(),
+            load: 0.0,
+            faction: Faction::Wild,
+            hp: body.max_hp(),
+            max_hp: body.max_hp(),
+            death: None,
+            statuses: Statuses::default(),
+            bounty: 0,
+            kills: 0,
+            corner: None,
             id: Instant::now(),
         }
     }
@@ -38,19 +74,53 @@ impl Actor {
     }
 
     pub fn render<G: Graphics>(&self, t: Matrix2d, g: &mut G) {
-        self.body.render(self.x, self.y, t, g);
+        self.body
+            .render(self.x, self.y, self.heading, self.faction.color(), t, g);
+        let top = self.y - self.body.dimensions().1 / 2.0 - 1.0;
+        self.statuses.render(self.x, top, t, g);
     }
 
-    pub fn render_extras<G: Graphics>(&self, actors: &Vec<Actor>, t: Matrix2d, g: &mut G) {
-        if let Some(sight) = self.sight {
+    pub fn render_extras<G: Graphics>(&self, actors: &[Actor], t: Matrix2d, g: &mut G) {
+        if let Some(sight) = self.current_sight() {
+            let color = [1.0, 1.0, 1.0, 0.3];
+            if self.fov >= std::f64::consts::PI * 2.0 {
+                ellipse(
+                    color,
+                    rectangle::centered_square(self.x, self.y, sight),
+                    t,
+                    g,
+                );
+            } else {
+                // Approximate the arc at the end of the cone with a handful of
+                // straight edges.
+                let steps = 16;
+                let start = self.heading - self.fov / 2.0;
+                let mut cone = vec![[self.x, self.y]];
+                for step in 0..=steps {
+                    let angle = start + self.fov * step as f64 / steps as f64;
+                    cone.push([self.x + sight * angle.cos(), self.y + sight * angle.sin()]);
+                }
+                polygon(color, &cone, t, g);
+            }
+        }
+        if let Some(weapon) = self.body.weapon() {
             ellipse(
-                [1.0, 1.0, 1.0, 0.3],
-                rectangle::centered_square(self.x, self.y, sight),
+                [1.0, 0.4, 0.4, 0.15],
+                rectangle::centered_square(self.x, self.y, weapon.range),
                 t,
                 g,
             );
         }
-        if let Some(ref task) = self.task {
+        // Fade out the spots where anyone out of sight was last seen.
+        for memory in self.perception.remembered() {
+            ellipse(
+                [0.6, 0.6, 1.0, 0.5 * memory.freshness() as f32],
+                rectangle::centered_square(memory.x, memory.y, ACTOR_REF_SIZE / 2.0),
+                t,
+                g,
+            );
+        }
+        if let Some(task) = self.tasks.front() {
             if let Some(target) = task.get_target_index() {
                 line(
                     [0.8, 0.2, 0.2, 1.0],
@@ -61,52 +131,144 @@ impl Actor {
                 );
             }
         }
+        // Trace out where the queued up tasks will take this actor.
+        let mut from = self.get_pos();
+        for task in self.tasks.iter() {
+            if let Some(to) = task.destination(actors) {
+                line(
+                    [0.9, 0.9, 0.2, 0.6],
+                    0.5,
+                    [from.0, from.1, to.0, to.1],
+                    t,
+                    g,
+                );
+                from = to;
+            }
+        }
     }
 
-    pub fn update_all(
-        dt: f64,
-        actors: &mut Vec<Actor>,
-        qt: &QuadTree<ActorRef>,
-        bounds: &WorldBounds,
-    ) -> UpdateResults {
+    pub fn update_all(actors: &mut [Actor], ctx: &TaskContext) -> UpdateResults {
         let mut new_actors = vec![];
         let mut dead_actors = vec![];
-        let mut new_animations = vec![];
+        let mut new_effects = vec![];
+        let mut new_projectiles = vec![];
+        Perception::update_all(actors, ctx);
         for i in 0..actors.len() {
-            let task_completion = Task::execute(i, dt, actors, qt, bounds);
+            // Anyone killed earlier this tick doesn't get a turn.
+            if actors[i].death.is_some() {
+                continue;
+            }
+            for damage in actors[i].statuses.update(ctx.dt) {
+                if actors[i].take_damage(damage) {
+                    dead_actors.push(i);
+                }
+            }
+            if actors[i].death.is_some() || !actors[i].statuses.can_act() {
+                continue;
+            }
+            ActorAi::update(i, actors, ctx);
+            let mut current = actors[i].tasks.pop_front();
+            let task_completion = match current {
+                Some(ref mut task) => task.execute(i, actors, ctx),
+                None => TaskCompletion::done(),
+            };
             if let Some(actor) = task_completion.new_actor {
                 new_actors.push(actor);
             }
-            if let Some(killed) = task_completion.dead_actors {
-                for actor_id in killed {
-                    if !dead_actors.contains(&actor_id) {
-                        dead_actors.push(actor_id);
-                    }
+            for (index, damage) in task_completion.damage {
+                if actors[index].take_damage(damage) {
+                    dead_actors.push(index);
                 }
             }
-            if let Some(animation) = task_completion.new_anim {
-                new_animations.push(animation);
+            if let Some(effect) = task_completion.new_effect {
+                new_effects.push(effect);
+            }
+            if let Some(projectile) = task_completion.new_projectile {
+                new_projectiles.push(projectile);
             }
             match task_completion.next_action {
-                NextAction::AiChoice => {
-                    actors[i].task = Some(ActorAi::get_task(
-                        i,
-                        actors,
-                        task_completion.prev_target,
-                        qt,
-                    ))
+                NextAction::Continue => {
+                    if let Some(task) = current {
+                        actors[i].tasks.push_front(task);
+                    }
                 }
-                NextAction::ChangeTo(next) => actors[i].task = Some(next),
-                NextAction::Continue => {}
+                NextAction::ChangeTo(next) => actors[i].tasks.push_front(next),
+                NextAction::Done => {}
+            }
+            // The AI only gets a say once everything queued up is finished.
+            if actors[i].tasks.is_empty() {
+                let next = ActorAi::get_task(i, actors, task_completion.prev_target, ctx);
+                actors[i].tasks.push_back(next);
             }
         }
         UpdateResults {
             new_actors,
             dead_actors,
-            new_animations,
+            new_effects,
+            new_projectiles,
         }
     }
 
+    /// Knock some HP off of this actor. Returns whether that killed it.
+    pub fn take_damage(&mut self, damage: Damage) -> bool {
+        if self.death.is_some() {
+            return false;
+        }
+        self.hp = (self.hp - damage.amount).max(0.0);
+        if self.hp > 0.0 {
+            return false;
+        }
+        self.death = Some(Death {
+            cause: damage.kind,
+            killer: damage.source,
+        });
+        true
+    }
+
+    /// Once actors have been removed, every target held by the tasks of those
+    /// left behind needs to be pointed back at the right index.
+    pub fn fix_targets(actors: &mut [Actor]) {
+        for i in 0..actors.len() {
+            let mut tasks = std::mem::take(&mut actors[i].tasks);
+            for task in tasks.iter_mut() {
+                task.fix_targets(actors);
+            }
+            actors[i].tasks = tasks;
+            let mut perception = std::mem::take(&mut actors[i].perception);
+            perception.fix_targets(actors);
+            actors[i].perception = perception;
+            let mut ai = std::mem::replace(&mut actors[i].ai, ActorAi::Wanderer);
+            ai.fix_targets(actors);
+            actors[i].ai = ai;
+        }
+    }
+
+    /// Give this actor a task on behalf of the player, either dropping
+    /// whatever it was doing or lining it up for after everything else.
+    pub fn order(&mut self, task: Task, queue: bool) {
+        if !queue {
+            self.tasks.clear();
+        }
+        self.tasks.push_back(task);
+    }
+
+    pub fn body(&self) -> ActorBody {
+        self.body
+    }
+
+    /// Take a building up to its next tier, if it has one.
+    pub fn upgrade(&mut self) {
+        if let ActorBody::Building(structure, tier) = self.body {
+            if tier + 1 < structure.tiers() {
+                self.body = ActorBody::Building(structure, tier + 1);
+            }
+        }
+    }
+
+    pub fn can_move(&self) -> bool {
+        self.speed.is_some()
+    }
+
     pub fn get_ref(&self, id: usize) -> ActorRef {
         ActorRef {
             id,
@@ -118,44 +280,76 @@ impl Actor {
         self.body.get_region(self.x, self.y)
     }
 
-    // TODO
-    // pub fn get_sight_range(&self) -> Option<Region> {
-    //     self.sight
-    //         .map(|sight| Region::new_circle(self.x, self.y, sight))
-    // }
-
+    /// Whether (x,y) is close enough and far enough in front of this actor
+    /// for it to be seen.
     pub fn can_see(&self, x: f64, y: f64) -> bool {
-        match self.sight {
+        if !self.within_sight(x, y) {
+            return false;
+        }
+        // Anything practically on top of the actor gets noticed no matter
+        // which way it's facing.
+        if vector::distance_cmp(self.x, self.y, x, y, ACTOR_REF_SIZE) {
+            return true;
+        }
+        let angle = (y - self.y).atan2(x - self.x) - self.heading;
+        // Wrap the difference into -PI..PI before comparing.
+        let angle = (angle + std::f64::consts::PI).rem_euclid(std::f64::consts::PI * 2.0)
+            - std::f64::consts::PI;
+        angle.abs() <= self.fov / 2.0
+    }
+
+    /// Whether (x,y) is close enough to see if the actor were facing it.
+    pub fn within_sight(&self, x: f64, y: f64) -> bool {
+        match self.current_sight() {
             Some(sight) => vector::distance_cmp(self.x, self.y, x, y, sight),
             None => false,
         }
     }
 
+    pub fn get_sight_range(&self) -> Option<Region> {
+        self.current_sight()
+            .map(|sight| Region::new_circle(self.x, self.y, sight))
+    }
+
     /// Returns true if the actor should no longer move, either because it
-    /// arrived at its destination or hit a grid boundary. Always returns false
-    /// if this actor can't move.
-    pub fn step_towards(&mut self, x: f64, y: f64, dt: f64, bounds: &WorldBounds) -> bool {
+    /// arrived at its destination or hit a grid boundary or a building. Always
+    /// returns false if this actor can't move.
+    pub fn step_towards(&mut self, x: f64, y: f64, dt: f64, map: &Map) -> bool {
+        let (from_x, from_y) = self.get_pos();
         let mut arrived = false;
-        match self.speed {
-            Some(speed) => {
-                let speed = speed * dt;
-                if vector::distance_cmp(self.x, self.y, x, y, speed) {
-                    self.x = x;
-                    self.y = y;
-                    arrived = true;
-                } else {
-                    self.step_in_dir(self.x, self.y, x, y, dt);
-                }
+        if let Some(speed) = self.current_speed() {
+            let speed = speed * dt;
+            if vector::distance_cmp(self.x, self.y, x, y, speed) {
+                self.x = x;
+                self.y = y;
+                arrived = true;
+            } else {
+                self.step_in_dir(self.x, self.y, x, y, dt);
             }
-            _ => (),
         }
-        self.constrain_location(bounds) || arrived
+        let stopped = self.constrain_location(&map.get_bounds()) || arrived;
+        self.keep_off_buildings(from_x, from_y, map) || stopped
     }
 
     /// Take a step in the direction directly away from (x,y)
-    pub fn step_from(&mut self, x: f64, y: f64, dt: f64, bounds: &WorldBounds) -> bool {
+    pub fn step_from(&mut self, x: f64, y: f64, dt: f64, map: &Map) -> bool {
+        let (from_x, from_y) = self.get_pos();
         self.step_in_dir(x, y, self.x, self.y, dt);
-        self.constrain_location(bounds)
+        let stopped = self.constrain_location(&map.get_bounds());
+        self.keep_off_buildings(from_x, from_y, map) || stopped
+    }
+
+    /// Go back to (x, y) if the actor has just moved onto ground that's been
+    /// built on. Anyone who was already there is free to walk off it. Returns
+    /// whether the move was undone.
+    pub fn keep_off_buildings(&mut self, x: f64, y: f64, map: &Map) -> bool {
+        if map.is_built_on(self.x, self.y) && !map.is_built_on(x, y) {
+            self.x = x;
+            self.y = y;
+            true
+        } else {
+            false
+        }
     }
 
     /// Constrain the current (x,y) position to be within the given bounds.
@@ -171,27 +365,92 @@ impl Actor {
         }
     }
 
-    pub fn description(i: usize, actors: &Vec<Actor>) -> String {
+    pub fn description(i: usize, actors: &[Actor]) -> String {
         let mut desc = String::new();
-        let ref actor = actors[i];
+        let actor = &actors[i];
         if let Some(name) = &actor.name {
             desc += format!("Name: {}\n", name).as_str();
         }
         desc += format!("Body: {}", actor.body).as_str();
+        desc += format!("\nFaction: {}", actor.faction).as_str();
+        desc += format!("\nHP: {:.0} of {}", actor.hp.ceil(), actor.max_hp).as_str();
         desc += format!("\nAI: {}", actor.ai).as_str();
-        if actor.task.is_some() {
-            desc += format!("\n\n{}", Task::description(i, actors)).as_str();
+        if let ActorBody::Building(structure, tier) = actor.body {
+            if structure.tiers() > 1 {
+                desc += format!("\nTier: {} of {}", tier + 1, structure.tiers()).as_str();
+            }
+        }
+        if let Some(weapon) = actor.body.weapon() {
+            desc += format!(
+                "\nRange: {}, damage: {}, shots per second: {}",
+                weapon.range, weapon.damage, weapon.fire_rate
+            )
+            .as_str();
+        }
+        if actor.bounty > 0 {
+            desc += format!("\nBounty: {}", actor.bounty).as_str();
+        }
+        if actor.kills > 0 {
+            desc += format!("\nKills: {}", actor.kills).as_str();
+        }
+        match &actor.ai {
+            ActorAi::Tree(behaviour) => {
+                desc += format!("\nBranch: {}", behaviour.running_branch()).as_str();
+            }
+            ActorAi::Utility(utility) => {
+                desc += format!("\n{}", utility.description()).as_str();
+            }
+            ActorAi::Tower(policy) => {
+                desc += format!("\nTargeting: {} (T to change)", policy).as_str();
+            }
+            ActorAi::Scripted(Scripted {
+                error: Some(error), ..
+            }) => {
+                desc += format!("\nScript error: {}", error).as_str();
+            }
+            _ => {}
+        }
+        if actor.sight.is_some() {
+            desc += format!("\n{}", actor.perception.description()).as_str();
+        }
+        if let Some(statuses) = actor.statuses.description() {
+            desc += format!("\n{}", statuses).as_str();
+        }
+        if actor.load > 0.0 {
+            desc += format!("\nCarrying: {:.1} of {}", actor.load, MAX_LOAD).as_str();
+        }
+        let mut tasks = actor.tasks.iter();
+        if let Some(task) = tasks.next() {
+            desc += format!("\n\nNow: {}", task.description(actors)).as_str();
+        }
+        for task in tasks {
+            desc += format!("\nThen: {}", task.description(actors)).as_str();
         }
 
         desc
     }
 
+    /// How fast the actor can move right now, after its statuses.
+    fn current_speed(&self) -> Option<f64> {
+        self.speed
+            .map(|speed| speed * self.statuses.speed_multiplier())
+    }
+
+    /// How far the actor can see right now, after its statuses.
+    fn current_sight(&self) -> Option<f64> {
+        self.sight
+            .map(|sight| sight * self.statuses.sight_multiplier())
+    }
+
     /// Step this actor's speed in direction from (x1,y1) to (x2,y2). Does
     /// nothing if actor can't move.
     /// DOES NOT constraint motion to within bounds.
     fn step_in_dir(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, dt: f64) {
-        if let Some(speed) = self.speed {
+        if let Some(speed) = self.current_speed() {
             let (dx, dy) = vector::direction(x1, y1, x2, y2);
+            if !dx.is_nan() && !dy.is_nan() {
+                self.heading = dy.atan2(dx);
+            }
             let (dx, dy) = vector::scale(dx, dy, speed * dt);
             self.x += dx;
             self.y += dy;
@@ -202,52 +461,177 @@ impl Actor {
 #[derive(Debug, Copy, Clone)]
 pub enum ActorBody {
     Worker,
-    Building,
+    /// Something built on the grid, taking up a whole number of cells, and
+    /// how many times it has been upgraded.
+    Building(Structure, usize),
 }
 
 impl ActorBody {
-    pub fn render<G: Graphics>(&self, x: f64, y: f64, t: Matrix2d, g: &mut G) {
+    pub fn render<G: Graphics>(
+        &self,
+        x: f64,
+        y: f64,
+        heading: f64,
+        color: Color,
+        t: Matrix2d,
+        g: &mut G,
+    ) {
         let render_callback = match self {
             ActorBody::Worker => ActorBody::worker_render,
-            ActorBody::Building => ActorBody::building_render,
+            ActorBody::Building(Structure::Tower, _) => ActorBody::tower_render,
+            ActorBody::Building(..) => ActorBody::building_render,
         };
-        render_callback(self, x, y, t, g);
+        render_callback(self, x, y, heading, color, t, g);
     }
 
     pub fn get_region(&self, x: f64, y: f64) -> Region {
-        let size = self.size();
         match self {
-            ActorBody::Worker => Region::new_circle(x, y, size / 2.0),
-            ActorBody::Building => {
-                let half_size = size / 2.0;
-                Region::new_rect(x - half_size, y - half_size, size, size)
+            ActorBody::Worker => Region::new_circle(x, y, self.size() / 2.0),
+            ActorBody::Building(..) => {
+                let (w, h) = self.dimensions();
+                Region::new_rect(x - w / 2.0, y - h / 2.0, w, h)
             }
         }
     }
 
-    fn worker_render<G: Graphics>(&self, x: f64, y: f64, t: Matrix2d, g: &mut G) {
-        let color = color::hex("ffffff");
+    fn worker_render<G: Graphics>(
+        &self,
+        x: f64,
+        y: f64,
+        heading: f64,
+        color: Color,
+        t: Matrix2d,
+        g: &mut G,
+    ) {
         let (x1, y1, x2, y2) = ActorBody::circle_position(x, y, self.size());
         ellipse(color, rectangle::rectangle_by_corners(x1, y1, x2, y2), t, g);
+        // A nose pointing whichever way the worker is facing.
+        let radius = self.size() / 2.0;
+        let (dx, dy) = (heading.cos() * radius, heading.sin() * radius);
+        line(color::hex("003333"), 1.0, [x, y, x + dx, y + dy], t, g);
     }
 
-    fn building_render<G: Graphics>(&self, x: f64, y: f64, t: Matrix2d, g: &mut G) {
-        let color = color::hex("888888");
-        rectangle(
-            color,
-            rectangle::centered_square(x, y, self.size() / 2.0),
-            t,
-            g,
-        );
+    fn building_render<G: Graphics>(
+        &self,
+        x: f64,
+        y: f64,
+        _heading: f64,
+        color: Color,
+        t: Matrix2d,
+        g: &mut G,
+    ) {
+        // Buildings are a darker shade of their faction's colour.
+        let color = [color[0] * 0.6, color[1] * 0.6, color[2] * 0.6, color[3]];
+        let (w, h) = self.dimensions();
+        rectangle(color, [x - w / 2.0, y - h / 2.0, w, h], t, g);
     }
 
-    fn size(&self) -> f64 {
+    fn tower_render<G: Graphics>(
+        &self,
+        x: f64,
+        y: f64,
+        heading: f64,
+        color: Color,
+        t: Matrix2d,
+        g: &mut G,
+    ) {
+        self.building_render(x, y, heading, color, t, g);
+        let tier = self.tier() as f64;
+        // A turret on top, with its barrel pointing at whatever it last shot.
+        // Both get bigger with every upgrade.
+        let radius = self.size() * (0.3 + tier * 0.05);
+        ellipse(color, rectangle::centered_square(x, y, radius), t, g);
+        let length = radius * (1.5 + tier * 0.25);
+        let (dx, dy) = (heading.cos() * length, heading.sin() * length);
+        line(color, 1.5 + tier * 0.5, [x, y, x + dx, y + dy], t, g);
+        // A pip in the corner for each upgrade.
+        let (w, h) = self.dimensions();
+        for pip in 0..self.tier() {
+            rectangle(
+                color::hex("ffee88"),
+                [
+                    x - w / 2.0 + 1.0 + pip as f64 * 3.0,
+                    y + h / 2.0 - 3.0,
+                    2.0,
+                    2.0,
+                ],
+                t,
+                g,
+            );
+        }
+    }
+
+    /// What this kind of body shoots with, if anything.
+    pub fn weapon(&self) -> Option<Weapon> {
         match self {
-            ActorBody::Worker => ACTOR_REF_SIZE,
-            ActorBody::Building => ACTOR_REF_SIZE * 2.0,
+            ActorBody::Worker => None,
+            ActorBody::Building(structure, tier) => structure.weapon(*tier),
         }
     }
 
+    /// How many times this body has been upgraded.
+    pub fn tier(&self) -> usize {
+        match self {
+            ActorBody::Worker => 0,
+            ActorBody::Building(_, tier) => *tier,
+        }
+    }
+
+    /// How many cells across and down this body takes up, if it sits on the
+    /// grid.
+    pub fn footprint(&self) -> Option<(usize, usize)> {
+        match self {
+            ActorBody::Worker => None,
+            ActorBody::Building(structure, _) => Some(structure.size()),
+        }
+    }
+
+    /// How much damage this kind of body can take before it dies.
+    pub fn max_hp(&self) -> f64 {
+        match self {
+            ActorBody::Worker => 30.0,
+            ActorBody::Building(Structure::Wall, _) => 50.0,
+            ActorBody::Building(..) => 100.0,
+        }
+    }
+
+    /// How far this kind of body can see, if it can see at all.
+    fn sight(&self) -> Option<f64> {
+        match self {
+            ActorBody::Worker => Some(50.0),
+            ActorBody::Building(..) => None,
+        }
+    }
+
+    /// How wide this kind of body's view is, in radians.
+    fn fov(&self) -> f64 {
+        match self {
+            ActorBody::Worker => std::f64::consts::PI * 2.0 / 3.0,
+            ActorBody::Building(..) => std::f64::consts::PI * 2.0,
+        }
+    }
+
+    /// The width and height of the body. Buildings leave a sliver of their
+    /// cells uncovered, so neighbours don't run together.
+    fn dimensions(&self) -> (f64, f64) {
+        match self {
+            ActorBody::Worker => (ACTOR_REF_SIZE, ACTOR_REF_SIZE),
+            ActorBody::Building(structure, _) => {
+                let (w, h) = structure.size();
+                (
+                    w as f64 * GRID_TILE_SIZE - 2.0,
+                    h as f64 * GRID_TILE_SIZE - 2.0,
+                )
+            }
+        }
+    }
+
+    /// The smaller of the body's width and height.
+    fn size(&self) -> f64 {
+        let (w, h) = self.dimensions();
+        w.min(h)
+    }
+
     /// Get a bounding rectangle for the circle with given (x,y) center and diameter
     pub fn circle_position(x: f64, y: f64, diameter: f64) -> (f64, f64, f64, f64) {
         let radius = diameter / 2.0;
@@ -257,13 +641,9 @@ impl ActorBody {
 
 impl fmt::Display for ActorBody {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ActorBody::Worker => "Worker",
-                ActorBody::Building => "Building",
-            }
-        )
+        match self {
+            ActorBody::Worker => write!(f, "Worker"),
+            ActorBody::Building(structure, _) => write!(f, "Building ({})", structure),
+        }
     }
 }
@@ -1,31 +1,99 @@
 use rand::Rng;
 
+use crate::world::behaviour::Behaviour;
+use crate::world::script::Scripted;
 use crate::world::*;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub enum ActorAi {
+    /// Never does anything.
+    Inert,
     Wanderer,
     Kamikaze,
-    Spawner { rate: f64 },
+    /// Turns out a new actor running `ai` every `rate` seconds.
+    Spawner {
+        rate: f64,
+        ai: Box<ActorAi>,
+    },
+    /// Makes its way along the roads to the goal.
+    Invader,
+    /// Shoots at whichever enemy in range the policy picks.
+    Tower(TargetPolicy),
+    Patroller,
+    /// Driven by a behaviour tree loaded from a data file.
+    Tree(Behaviour),
+    /// Picks whatever best meets its needs at the time.
+    Utility(Utility),
+    /// Asks a script what to do.
+    Scripted(Scripted),
 }
 
 impl ActorAi {
     pub fn get_task(
         i: usize,
-        actors: &mut Vec<Actor>,
+        actors: &mut [Actor],
         prev_target: Option<Target>,
-        _qt: &QuadTree<ActorRef>,
+        ctx: &TaskContext,
     ) -> Task {
         use ActorAi::*;
         match actors[i].ai {
+            Inert => Task::idle(),
             Wanderer => wanderer_callback(i, actors),
-            Kamikaze => kamikaze_callback(i, actors, prev_target),
-            Spawner { rate } => spawn_callback(i, actors, rate),
+            Kamikaze => kamikaze_callback(i, actors, prev_target, ctx.relations),
+            Spawner { .. } => spawn_callback(i, actors),
+            Invader => invader_callback(i, actors, ctx),
+            Tower(policy) => tower_callback(i, actors, policy, ctx),
+            Patroller => patrol_callback(i, actors),
+            Tree(ref behaviour) => {
+                let mut behaviour = behaviour.clone();
+                let task = behaviour.next_task(i, actors, prev_target, ctx.relations);
+                actors[i].ai = Tree(behaviour);
+                task
+            }
+            Utility(ref utility) => {
+                let mut utility = utility.clone();
+                let task = utility.next_task(i, actors, ctx);
+                actors[i].ai = Utility(utility);
+                task
+            }
+            Scripted(ref scripted) => {
+                let mut scripted = scripted.clone();
+                let task = scripted.next_task(i, actors, ctx);
+                actors[i].ai = Scripted(scripted);
+                task
+            }
+        }
+    }
+
+    /// Anything the AI keeps track of that changes every tick, whether or not
+    /// it's deciding what to do next.
+    pub fn update(i: usize, actors: &mut [Actor], ctx: &TaskContext) {
+        if let ActorAi::Utility(_) = actors[i].ai {
+            let threat = utility::threat_distance(i, actors, ctx.relations);
+            let actor = &mut actors[i];
+            if let ActorAi::Utility(utility) = &mut actor.ai {
+                utility.update(ctx.dt, actor.tasks.front(), threat);
+            }
+        }
+    }
+
+    /// Whether other actors should steer clear of this one.
+    pub fn is_dangerous(&self) -> bool {
+        match self {
+            ActorAi::Kamikaze => true,
+            ActorAi::Tree(behaviour) => behaviour.tree.is_dangerous(),
+            _ => false,
+        }
+    }
+
+    pub fn fix_targets(&mut self, actors: &[Actor]) {
+        if let ActorAi::Tree(behaviour) = self {
+            behaviour.fix_targets(actors);
         }
     }
 }
 
-fn wanderer_callback(i: usize, actors: &mut Vec<Actor>) -> Task {
+fn wanderer_callback(i: usize, actors: &mut [Actor]) -> Task {
     let (x, y) = actors[i].get_pos();
     Task::move_to(
         x + (rand::thread_rng().gen::<f64>() - 0.5) * 25.0,
@@ -33,13 +101,26 @@ fn wanderer_callback(i: usize, actors: &mut Vec<Actor>) -> Task {
     )
 }
 
-fn kamikaze_callback(i: usize, actors: &mut Vec<Actor>, prev_target: Option<Target>) -> Task {
+fn kamikaze_callback(
+    i: usize,
+    actors: &mut [Actor],
+    prev_target: Option<Target>,
+    relations: &Relations,
+) -> Task {
+    let perception = &actors[i].perception;
+    let faction = actors[i].faction;
+    let hostile = |other: &Actor| relations.is_hostile(faction, other.faction);
     let t = match prev_target {
         Some(target) => target,
-        None => {
-            let new_target = rand::thread_rng().gen_range(0, actors.len());
-            Target::new(new_target, actors[new_target].id)
-        }
+        None => match perception.nearest(i, actors, hostile) {
+            Some((nearest, _)) => Target::new(nearest, actors[nearest].id),
+            // Nobody in sight, so go looking where somebody was last seen,
+            // or just wander about if there's nobody to look for.
+            None => match perception.freshest_memory(actors, hostile) {
+                Some(memory) => return Task::move_to(memory.x, memory.y),
+                None => return wanderer_callback(i, actors),
+            },
+        },
     };
     if t.id == actors[i].id || t.index.is_none() {
         return Task::idle();
@@ -49,24 +130,94 @@ fn kamikaze_callback(i: usize, actors: &mut Vec<Actor>, prev_target: Option<Targ
     if vector::distance_cmp(x, y, tx, ty, 25.0) {
         Task::explode()
     } else {
-        Task::move_to_actor(t, 20.0)
+        // Close in until they're inside the blast radius, but don't chase
+        // forever; pick someone else if they're too slippery.
+        let near = Condition::Near {
+            target: t.clone(),
+            distance: 25.0,
+        };
+        Task::timeout(
+            10.0,
+            Task::race_until(near, Task::move_to_actor(t, 0.0)),
+            None,
+        )
+    }
+}
+
+/// Walk back and forth between where the actor is now and a spot a short way
+/// off in a random direction until someone else gets in the way, then start a
+/// new beat from wherever that happened.
+fn patrol_callback(i: usize, actors: &mut [Actor]) -> Task {
+    let (x, y) = actors[i].get_pos();
+    let angle = rand::thread_rng().gen::<f64>() * std::f64::consts::PI * 2.0;
+    let (dx, dy) = vector::scale(angle.cos(), angle.sin(), 100.0);
+    Task::race_until(
+        Condition::Crowded { distance: 30.0 },
+        Task::repeat(
+            None,
+            Task::sequence(vec![Task::move_to(x + dx, y + dy), Task::move_to(x, y)]),
+        ),
+    )
+}
+
+fn spawn_callback(i: usize, actors: &mut [Actor]) -> Task {
+    match &actors[i].ai {
+        ActorAi::Spawner { rate, ai } => {
+            Task::spawn(*rate, 0.0, 0.0, (**ai).clone(), ActorBody::Worker)
+        }
+        _ => Task::idle(),
+    }
+}
+
+/// Shoot at whoever the policy picks, or keep watch if there's nobody in
+/// range.
+fn tower_callback(i: usize, actors: &mut [Actor], policy: TargetPolicy, ctx: &TaskContext) -> Task {
+    let weapon = match actors[i].body().weapon() {
+        Some(weapon) => weapon,
+        None => return Task::idle(),
+    };
+    match policy.pick(i, weapon.range, actors, ctx) {
+        Some(target) => Task::fire(Target::new(target, actors[target].id), weapon),
+        None => Task::idle(),
     }
 }
 
-fn spawn_callback(_i: usize, _actors: &mut Vec<Actor>, rate: f64) -> Task {
-    Task::spawn(rate, 10.0, 10.0, ActorAi::Wanderer, ActorBody::Worker)
+/// Take the roads from wherever the actor is to the goal. Anyone who has
+/// wandered off the roads heads straight for it instead.
+fn invader_callback(i: usize, actors: &mut [Actor], ctx: &TaskContext) -> Task {
+    let map = ctx.map;
+    let goal = match map.goal {
+        Some(goal) => goal,
+        None => return Task::idle(),
+    };
+    let (x, y) = actors[i].get_pos();
+    let path = map.cell_at(x, y).and_then(|cell| map.road_path(cell, goal));
+    match path {
+        Some(path) => Task::follow_path(
+            path.into_iter()
+                .map(|(cell_x, cell_y)| map.cell_center(cell_x, cell_y))
+                .collect(),
+        ),
+        None => {
+            let (goal_x, goal_y) = map.cell_center(goal.0, goal.1);
+            Task::move_to(goal_x, goal_y)
+        }
+    }
 }
 
 impl fmt::Display for ActorAi {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ActorAi::Wanderer => "Wanderer",
-                ActorAi::Kamikaze => "Bomber",
-                ActorAi::Spawner { .. } => "Spawner",
-            }
-        )
+        match self {
+            ActorAi::Inert => write!(f, "None"),
+            ActorAi::Wanderer => write!(f, "Wanderer"),
+            ActorAi::Kamikaze => write!(f, "Bomber"),
+            ActorAi::Spawner { .. } => write!(f, "Spawner"),
+            ActorAi::Invader => write!(f, "Invader"),
+            ActorAi::Tower(_) => write!(f, "Tower"),
+            ActorAi::Patroller => write!(f, "Patroller"),
+            ActorAi::Tree(behaviour) => write!(f, "Tree ({})", behaviour.tree.name),
+            ActorAi::Utility(_) => write!(f, "Utility"),
+            ActorAi::Scripted(scripted) => write!(f, "Script ({})", scripted.name),
+        }
     }
 }
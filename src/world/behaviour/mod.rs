@@ -0,0 +1,487 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::world::*;
+
+mod parse;
+
+pub use parse::ParseError;
+
+/// A behaviour tree loaded from a data file. The tree itself never changes
+/// once loaded, so every actor using it shares the same copy.
+#[derive(Debug)]
+pub struct BehaviourTree {
+    pub name: String,
+    root: Node,
+}
+
+#[derive(Debug)]
+pub enum Node {
+    /// Try each child in order until one doesn't fail. Always starts again
+    /// from the first child, so earlier branches take priority.
+    Selector {
+        name: Option<String>,
+        children: Vec<Node>,
+    },
+    /// Run each child in order until one fails. Picks up where it left off
+    /// when the task it handed out is finished.
+    Sequence {
+        name: Option<String>,
+        children: Vec<Node>,
+    },
+    Decorator(Decorator, Box<Node>),
+    Check(Check),
+    Action(Action),
+}
+
+/// Changes the result of a single child, or the task it hands out.
+#[derive(Debug)]
+pub enum Decorator {
+    /// Swap success and failure.
+    Invert,
+    /// Succeed even if the child fails.
+    Succeed,
+    /// Give the child's task this many seconds to finish.
+    Timeout(f64),
+    /// Run the child's task this many times, or forever.
+    Repeat(Option<u32>),
+}
+
+/// A question about the world, answered straight away.
+#[derive(Debug)]
+pub enum Check {
+    HasTarget,
+    TargetNear(f64),
+    Crowded(f64),
+    Chance(f64),
+}
+
+/// Something for the actor to do. Most actions hand out a task, and count as
+/// running until it's done.
+#[derive(Debug)]
+pub enum Action {
+    Idle,
+    Wander(f64),
+    Patrol(f64),
+    Chase(f64),
+    Flee(f64),
+    Explode,
+    /// Pick a random actor in sight as the new target. Doesn't take any
+    /// time.
+    PickTarget,
+    /// Go and look where somebody was last seen.
+    Investigate,
+    /// Drop the current target. Doesn't take any time.
+    Forget,
+}
+
+enum Status {
+    Success,
+    Failure,
+    Running(Task),
+}
+
+/// Everything a tree needs to know about the world while it decides what an
+/// actor should do next.
+struct TickContext<'a> {
+    i: usize,
+    actors: &'a [Actor],
+    target: &'a mut Option<Target>,
+    relations: &'a Relations,
+    /// Child indices leading to the action that's running, innermost first.
+    path: Vec<usize>,
+}
+
+/// One actor's use of a behaviour tree, along with what it was up to.
+#[derive(Debug, Clone)]
+pub struct Behaviour {
+    pub tree: Rc<BehaviourTree>,
+    running: Vec<usize>,
+    target: Option<Target>,
+}
+
+impl BehaviourTree {
+    /// Whether anything in this tree could make the actor blow up.
+    pub fn is_dangerous(&self) -> bool {
+        self.root
+            .any(&|node| matches!(node, Node::Action(Action::Explode)))
+    }
+}
+
+impl Behaviour {
+    pub fn new(tree: Rc<BehaviourTree>) -> Self {
+        Self {
+            tree,
+            running: vec![],
+            target: None,
+        }
+    }
+
+    /// Work through the tree to find the actor's next task. If nothing in the
+    /// tree has anything for it to do, it idles until the next tick.
+    pub fn next_task(
+        &mut self,
+        i: usize,
+        actors: &[Actor],
+        prev_target: Option<Target>,
+        relations: &Relations,
+    ) -> Task {
+        if prev_target.is_some() {
+            self.target = prev_target;
+        }
+        let mut ctx = TickContext {
+            i,
+            actors,
+            target: &mut self.target,
+            relations,
+            path: vec![],
+        };
+        let resume = if self.running.is_empty() {
+            None
+        } else {
+            Some(self.running.as_slice())
+        };
+        let status = self.tree.root.tick(resume, &mut ctx);
+        let mut path = ctx.path;
+        path.reverse();
+        self.running = path;
+        match status {
+            Status::Running(task) => task,
+            Status::Success | Status::Failure => {
+                self.running.clear();
+                Task::idle()
+            }
+        }
+    }
+
+    /// The chain of nodes that led to the task the actor is working on.
+    pub fn running_branch(&self) -> String {
+        if self.running.is_empty() {
+            return "nothing".into();
+        }
+        let mut labels = vec![];
+        let mut node = &self.tree.root;
+        let mut path = self.running.iter();
+        loop {
+            labels.push(node.to_string());
+            node = match node {
+                Node::Selector { children, .. } | Node::Sequence { children, .. } => {
+                    match path.next().and_then(|&index| children.get(index)) {
+                        Some(child) => child,
+                        None => break,
+                    }
+                }
+                Node::Decorator(_, child) => child,
+                Node::Check(_) | Node::Action(_) => break,
+            };
+        }
+        labels.join(" > ")
+    }
+
+    pub fn fix_targets(&mut self, actors: &[Actor]) {
+        if let Some(target) = self.target.as_mut() {
+            target.fix(actors);
+        }
+    }
+}
+
+impl Node {
+    fn any(&self, f: &dyn Fn(&Node) -> bool) -> bool {
+        f(self)
+            || match self {
+                Node::Selector { children, .. } | Node::Sequence { children, .. } => {
+                    children.iter().any(|child| child.any(f))
+                }
+                Node::Decorator(_, child) => child.any(f),
+                Node::Check(_) | Node::Action(_) => false,
+            }
+    }
+
+    /// `resume` is the path to the action that was running last time, if it
+    /// runs through this node.
+    fn tick(&self, resume: Option<&[usize]>, ctx: &mut TickContext) -> Status {
+        match self {
+            Node::Selector { children, .. } => {
+                let resume = resume.and_then(|path| path.split_first());
+                for (index, child) in children.iter().enumerate() {
+                    let child_resume = resume.filter(|r| *r.0 == index).map(|r| r.1);
+                    match child.tick(child_resume, ctx) {
+                        Status::Failure => continue,
+                        Status::Running(task) => {
+                            ctx.path.push(index);
+                            return Status::Running(task);
+                        }
+                        Status::Success => return Status::Success,
+                    }
+                }
+                Status::Failure
+            }
+            Node::Sequence { children, .. } => {
+                let resume = resume.and_then(|path| path.split_first());
+                let start = resume.map(|r| *r.0).unwrap_or(0);
+                for (index, child) in children.iter().enumerate().skip(start) {
+                    let child_resume = resume.filter(|r| *r.0 == index).map(|r| r.1);
+                    match child.tick(child_resume, ctx) {
+                        Status::Success => continue,
+                        Status::Running(task) => {
+                            ctx.path.push(index);
+                            return Status::Running(task);
+                        }
+                        Status::Failure => return Status::Failure,
+                    }
+                }
+                Status::Success
+            }
+            // Decorators don't take up a spot in the path, so the whole thing
+            // gets passed straight down.
+            Node::Decorator(decorator, child) => decorator.apply(child.tick(resume, ctx)),
+            Node::Check(check) => {
+                if check.passes(ctx) {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Node::Action(action) => {
+                // Coming back to a running action means its task is done.
+                if resume.is_some() {
+                    return Status::Success;
+                }
+                action.start(ctx)
+            }
+        }
+    }
+}
+
+impl Decorator {
+    fn apply(&self, status: Status) -> Status {
+        match (self, status) {
+            (Decorator::Invert, Status::Success) => Status::Failure,
+            (Decorator::Invert, Status::Failure) => Status::Success,
+            (Decorator::Succeed, Status::Failure) => Status::Success,
+            (Decorator::Timeout(seconds), Status::Running(task)) => {
+                Status::Running(Task::timeout(*seconds, task, None))
+            }
+            (Decorator::Repeat(times), Status::Running(task)) => {
+                Status::Running(Task::repeat(*times, task))
+            }
+            (_, status) => status,
+        }
+    }
+}
+
+impl Check {
+    fn passes(&self, ctx: &TickContext) -> bool {
+        let (x, y) = ctx.actors[ctx.i].get_pos();
+        match self {
+            Check::HasTarget => target_index(ctx).is_some(),
+            Check::TargetNear(distance) => match target_index(ctx) {
+                Some(index) => {
+                    let (tx, ty) = ctx.actors[index].get_pos();
+                    vector::distance_cmp(x, y, tx, ty, *distance)
+                }
+                None => false,
+            },
+            Check::Crowded(distance) => nearest_within(ctx, *distance).is_some(),
+            Check::Chance(chance) => rand::thread_rng().gen::<f64>() < *chance,
+        }
+    }
+}
+
+impl Action {
+    fn start(&self, ctx: &mut TickContext) -> Status {
+        let (x, y) = ctx.actors[ctx.i].get_pos();
+        let task = match self {
+            Action::Idle => Task::idle(),
+            Action::Wander(distance) => {
+                let (dx, dy) = random_offset(*distance);
+                Task::move_to(x + dx, y + dy)
+            }
+            Action::Patrol(distance) => {
+                let (dx, dy) = random_offset(*distance);
+                Task::sequence(vec![Task::move_to(x + dx, y + dy), Task::move_to(x, y)])
+            }
+            Action::Chase(distance) => match (target_index(ctx), ctx.target.clone()) {
+                (Some(_), Some(target)) => Task::move_to_actor(target, *distance),
+                _ => return Status::Failure,
+            },
+            Action::Flee(distance) => match nearest_within(ctx, *distance) {
+                Some(index) => Task::run_from(index, ctx.actors[index].id),
+                None => return Status::Failure,
+            },
+            Action::Explode => Task::explode(),
+            Action::PickTarget => {
+                let visible: Vec<usize> = ctx.actors[ctx.i]
+                    .perception
+                    .visible()
+                    .filter(|&index| is_hostile(ctx, index))
+                    .collect();
+                if visible.is_empty() {
+                    return Status::Failure;
+                }
+                let index = visible[rand::thread_rng().gen_range(0, visible.len())];
+                *ctx.target = Some(Target::new(index, ctx.actors[index].id));
+                return Status::Success;
+            }
+            Action::Investigate => {
+                match ctx.actors[ctx.i]
+                    .perception
+                    .freshest_memory(ctx.actors, |other| {
+                        ctx.relations
+                            .is_hostile(ctx.actors[ctx.i].faction, other.faction)
+                    }) {
+                    Some(memory) => Task::move_to(memory.x, memory.y),
+                    None => return Status::Failure,
+                }
+            }
+            Action::Forget => {
+                *ctx.target = None;
+                return Status::Success;
+            }
+        };
+        Status::Running(task)
+    }
+}
+
+/// The index of the current target, as long as it's still around and isn't
+/// the actor itself.
+fn target_index(ctx: &TickContext) -> Option<usize> {
+    ctx.target
+        .as_ref()
+        .and_then(|target| target.index)
+        .filter(|&index| index != ctx.i)
+}
+
+/// The closest hostile actor in sight within `distance`, if there is one.
+fn nearest_within(ctx: &TickContext, distance: f64) -> Option<usize> {
+    let faction = ctx.actors[ctx.i].faction;
+    ctx.actors[ctx.i]
+        .perception
+        .nearest(ctx.i, ctx.actors, |other| {
+            ctx.relations.is_hostile(faction, other.faction)
+        })
+        .filter(|&(_, nearest)| nearest <= distance)
+        .map(|(index, _)| index)
+}
+
+fn random_offset(distance: f64) -> (f64, f64) {
+    let angle = rand::thread_rng().gen::<f64>() * std::f64::consts::PI * 2.0;
+    vector::scale(angle.cos(), angle.sin(), distance)
+}
+
+/// Every behaviour tree found in a directory, keyed by file name.
+#[derive(Default)]
+pub struct Behaviours {
+    trees: HashMap<String, Rc<BehaviourTree>>,
+}
+
+impl Behaviours {
+    /// Load every `.bt` file in `dir`. Files that can't be read or parsed are
+    /// reported and left out.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Self {
+        let mut behaviours = Behaviours::default();
+        let entries = match fs::read_dir(dir.as_ref()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", dir.as_ref().display(), e);
+                return behaviours;
+            }
+        };
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension() != Some("bt".as_ref()) {
+                continue;
+            }
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let tree = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| BehaviourTree::parse(&name, &source).map_err(|e| e.to_string()));
+            match tree {
+                Ok(tree) => {
+                    behaviours.trees.insert(name, Rc::new(tree));
+                }
+                Err(e) => eprintln!("Couldn't load {}: {}", path.display(), e),
+            }
+        }
+        behaviours
+    }
+
+    /// An AI running the named tree, if it was loaded.
+    pub fn ai(&self, name: &str) -> Option<ActorAi> {
+        self.trees
+            .get(name)
+            .map(|tree| ActorAi::Tree(Behaviour::new(tree.clone())))
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Selector {
+                name: Some(name), ..
+            }
+            | Node::Sequence {
+                name: Some(name), ..
+            } => {
+                write!(f, "{}", name)
+            }
+            Node::Selector { name: None, .. } => write!(f, "selector"),
+            Node::Sequence { name: None, .. } => write!(f, "sequence"),
+            Node::Decorator(decorator, _) => write!(f, "{}", decorator),
+            Node::Check(check) => write!(f, "{}", check),
+            Node::Action(action) => write!(f, "{}", action),
+        }
+    }
+}
+
+impl fmt::Display for Decorator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decorator::Invert => write!(f, "not"),
+            Decorator::Succeed => write!(f, "succeed"),
+            Decorator::Timeout(seconds) => write!(f, "timeout {}", seconds),
+            Decorator::Repeat(Some(times)) => write!(f, "repeat {}", times),
+            Decorator::Repeat(None) => write!(f, "repeat forever"),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::HasTarget => write!(f, "has_target"),
+            Check::TargetNear(distance) => write!(f, "target_near {}", distance),
+            Check::Crowded(distance) => write!(f, "crowded {}", distance),
+            Check::Chance(chance) => write!(f, "chance {}", chance),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Idle => write!(f, "idle"),
+            Action::Wander(distance) => write!(f, "wander {}", distance),
+            Action::Patrol(distance) => write!(f, "patrol {}", distance),
+            Action::Chase(distance) => write!(f, "chase {}", distance),
+            Action::Flee(distance) => write!(f, "flee {}", distance),
+            Action::Explode => write!(f, "explode"),
+            Action::PickTarget => write!(f, "pick_target"),
+            Action::Investigate => write!(f, "investigate"),
+            Action::Forget => write!(f, "forget"),
+        }
+    }
+}
+
+fn is_hostile(ctx: &TickContext, index: usize) -> bool {
+    ctx.relations
+        .is_hostile(ctx.actors[ctx.i].faction, ctx.actors[index].faction)
+}
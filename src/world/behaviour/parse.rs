@@ -0,0 +1,283 @@
+//! Behaviour trees are written one node per line, with children indented
+//! further than their parent:
+//!
+//! ```text
+//! # Anything after a '#' is ignored.
+//! selector
+//!     sequence attack
+//!         has_target
+//!         target_near 25
+//!         explode
+//!     pick_target
+//! ```
+//!
+//! Selectors and sequences can be given a name, which is what gets shown for
+//! them in the selected actor's description.
+
+use std::fmt;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A single non-blank line of a tree file.
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    words: Vec<&'a str>,
+}
+
+impl BehaviourTree {
+    pub fn parse(name: &str, source: &str) -> Result<Self, ParseError> {
+        let lines: Vec<Line> = source
+            .lines()
+            .enumerate()
+            .filter_map(|(number, text)| {
+                let text = text.split('#').next().unwrap_or("");
+                let words: Vec<&str> = text.split_whitespace().collect();
+                if words.is_empty() {
+                    return None;
+                }
+                let indent = text
+                    .chars()
+                    .take_while(|c| c.is_whitespace())
+                    .map(|c| if c == '\t' { 4 } else { 1 })
+                    .sum();
+                Some(Line {
+                    number: number + 1,
+                    indent,
+                    words,
+                })
+            })
+            .collect();
+        let first = match lines.first() {
+            Some(first) => first,
+            None => {
+                return Err(ParseError {
+                    line: 1,
+                    message: "the tree is empty".into(),
+                })
+            }
+        };
+        let mut next = 0;
+        let root = parse_node(&lines, &mut next)?;
+        if let Some(extra) = lines.get(next) {
+            return Err(error(
+                extra,
+                format!(
+                    "only one root node is allowed, but this is indented the same as line {}",
+                    first.number
+                ),
+            ));
+        }
+        Ok(BehaviourTree {
+            name: name.into(),
+            root,
+        })
+    }
+}
+
+/// Parse the node at `lines[*next]` along with all of its children, leaving
+/// `next` pointing just past them.
+fn parse_node(lines: &[Line], next: &mut usize) -> Result<Node, ParseError> {
+    let line = &lines[*next];
+    *next += 1;
+    let mut children = vec![];
+    if let Some(child) = lines.get(*next) {
+        if child.indent > line.indent {
+            let indent = child.indent;
+            while let Some(child) = lines.get(*next) {
+                if child.indent <= line.indent {
+                    break;
+                }
+                if child.indent != indent {
+                    return Err(error(child, "inconsistent indentation".into()));
+                }
+                children.push(parse_node(lines, next)?);
+            }
+        }
+    }
+    let keyword = line.words[0];
+    let args = &line.words[1..];
+    let node = match keyword {
+        "selector" | "sequence" => {
+            if children.is_empty() {
+                return Err(error(line, format!("{} needs children", keyword)));
+            }
+            if args.len() > 1 {
+                return Err(error(line, format!("{} takes at most a name", keyword)));
+            }
+            let name = args.first().map(|name| name.to_string());
+            if keyword == "selector" {
+                Node::Selector { name, children }
+            } else {
+                Node::Sequence { name, children }
+            }
+        }
+        "not" | "succeed" | "timeout" | "repeat" => {
+            if children.len() != 1 {
+                return Err(error(line, format!("{} needs exactly one child", keyword)));
+            }
+            let decorator = match keyword {
+                "not" => no_args(line, Decorator::Invert)?,
+                "succeed" => no_args(line, Decorator::Succeed)?,
+                "timeout" => Decorator::Timeout(number_arg(line)?),
+                _ => Decorator::Repeat(repeat_arg(line)?),
+            };
+            Node::Decorator(decorator, Box::new(children.remove(0)))
+        }
+        _ => {
+            if !children.is_empty() {
+                return Err(error(line, format!("{} can't have children", keyword)));
+            }
+            match keyword {
+                "has_target" => Node::Check(no_args(line, Check::HasTarget)?),
+                "target_near" => Node::Check(Check::TargetNear(number_arg(line)?)),
+                "crowded" => Node::Check(Check::Crowded(number_arg(line)?)),
+                "chance" => Node::Check(Check::Chance(number_arg(line)?)),
+                "idle" => Node::Action(no_args(line, Action::Idle)?),
+                "wander" => Node::Action(Action::Wander(number_arg(line)?)),
+                "patrol" => Node::Action(Action::Patrol(number_arg(line)?)),
+                "chase" => Node::Action(Action::Chase(number_arg(line)?)),
+                "flee" => Node::Action(Action::Flee(number_arg(line)?)),
+                "explode" => Node::Action(no_args(line, Action::Explode)?),
+                "pick_target" => Node::Action(no_args(line, Action::PickTarget)?),
+                "forget" => Node::Action(no_args(line, Action::Forget)?),
+                "investigate" => Node::Action(no_args(line, Action::Investigate)?),
+                _ => return Err(error(line, format!("unknown node \"{}\"", keyword))),
+            }
+        }
+    };
+    Ok(node)
+}
+
+fn no_args<T>(line: &Line, value: T) -> Result<T, ParseError> {
+    if line.words.len() == 1 {
+        Ok(value)
+    } else {
+        Err(error(
+            line,
+            format!("{} doesn't take anything", line.words[0]),
+        ))
+    }
+}
+
+fn number_arg(line: &Line) -> Result<f64, ParseError> {
+    match line.words[1..] {
+        [arg] => arg.parse().map_err(|_| {
+            error(
+                line,
+                format!("{} needs a number, not \"{}\"", line.words[0], arg),
+            )
+        }),
+        _ => Err(error(line, format!("{} needs a number", line.words[0]))),
+    }
+}
+
+fn repeat_arg(line: &Line) -> Result<Option<u32>, ParseError> {
+    match line.words[1..] {
+        ["forever"] => Some(None),
+        [times] => times.parse().ok().filter(|&times| times > 0).map(Some),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        error(
+            line,
+            format!("repeat needs a count from 1 to {} or \"forever\"", u32::MAX),
+        )
+    })
+}
+
+fn error(line: &Line, message: String) -> ParseError {
+    ParseError {
+        line: line.number,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested() {
+        let source = "
+# A comment on its own.
+selector
+    sequence attack # And one after a node.
+        has_target
+        target_near 25
+        explode
+    timeout 10
+        chase 20
+    pick_target
+";
+        let tree = BehaviourTree::parse("test", source).unwrap();
+        assert_eq!(tree.name, "test");
+        match tree.root {
+            Node::Selector { name, children } => {
+                assert!(name.is_none());
+                assert_eq!(children.len(), 3);
+                match &children[0] {
+                    Node::Sequence { name, children } => {
+                        assert_eq!(name.as_ref().unwrap(), "attack");
+                        assert_eq!(children.len(), 3);
+                    }
+                    other => panic!("expected a sequence, got {:?}", other),
+                }
+                match &children[1] {
+                    Node::Decorator(Decorator::Timeout(seconds), child) => {
+                        assert_eq!(*seconds, 10.0);
+                        assert_eq!(child.to_string(), "chase 20");
+                    }
+                    other => panic!("expected a timeout, got {:?}", other),
+                }
+                assert_eq!(children[2].to_string(), "pick_target");
+            }
+            other => panic!("expected a selector, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("", 1),
+            ("selector", 1),
+            ("sequence\n    explode\n  explode", 3),
+            ("sequence\n    explode\nexplode", 3),
+            ("not\n    explode\n    explode", 1),
+            ("explode\n    idle", 1),
+            ("selector\n    chase far", 2),
+            ("selector\n    teleport", 2),
+            ("repeat sometimes\n    idle", 1),
+            ("repeat 0\n    idle", 1),
+        ];
+        for (source, line) in cases.iter() {
+            match BehaviourTree::parse("test", source) {
+                Ok(tree) => panic!("{:?} parsed as {:?}", source, tree),
+                Err(e) => assert_eq!(e.line, *line, "{:?}: {}", source, e),
+            }
+        }
+    }
+
+    #[test]
+    fn shipped_trees_parse() {
+        for entry in fs::read_dir("assets/ai").unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            if let Err(e) = BehaviourTree::parse("test", &source) {
+                panic!("{}: {}", path.display(), e);
+            }
+        }
+    }
+}
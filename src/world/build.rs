@@ -0,0 +1,199 @@
+use graphics::math::Matrix2d;
+use graphics::*;
+
+use crate::world::map::Map;
+use crate::world::*;
+
+/// Something the player can build.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Structure {
+    /// Gets in the way, and nothing else.
+    Wall,
+    Tower,
+    /// Turns out workers for the player.
+    Barracks,
+}
+
+/// Why a structure can't go where the player is pointing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Part of it would be on ground that can't be built on, or that already
+    /// has something built on it.
+    Blocked,
+    /// Somebody is standing in the way.
+    Occupied,
+    /// It would leave the invaders no way through to the goal.
+    CutsOffGoal,
+    /// The player is `short` of being able to pay for it.
+    TooExpensive { short: u32 },
+}
+
+/// Where a structure would go if the player placed it now, and whether it
+/// can go there.
+pub struct Blueprint {
+    pub structure: Structure,
+    /// The top left cell of its footprint.
+    pub corner: (usize, usize),
+    pub problem: Option<Problem>,
+}
+
+impl Structure {
+    /// The structure after this one, for cycling through them all.
+    pub fn next(self) -> Self {
+        match self {
+            Structure::Wall => Structure::Tower,
+            Structure::Tower => Structure::Barracks,
+            Structure::Barracks => Structure::Wall,
+        }
+    }
+
+    /// How many cells across and down it takes up.
+    pub fn size(self) -> (usize, usize) {
+        match self {
+            Structure::Wall => (1, 1),
+            Structure::Tower => (2, 2),
+            Structure::Barracks => (3, 2),
+        }
+    }
+
+    /// How many tiers it comes in, counting the one it's built at.
+    pub fn tiers(self) -> usize {
+        self.costs().len()
+    }
+
+    /// How much it costs to put up at first.
+    pub fn cost(self) -> u32 {
+        self.costs()[0]
+    }
+
+    /// How much it costs to go from `tier` up to the next one, if there's one
+    /// to go to.
+    pub fn upgrade_cost(self, tier: usize) -> Option<u32> {
+        self.costs().get(tier + 1).copied()
+    }
+
+    /// Everything spent on one built up to `tier`.
+    pub fn invested(self, tier: usize) -> u32 {
+        self.costs().iter().take(tier + 1).sum()
+    }
+
+    /// What it shoots with at `tier`, if anything.
+    pub fn weapon(self, tier: usize) -> Option<Weapon> {
+        match self {
+            Structure::Tower => Some(Weapon {
+                range: 80.0 + tier as f64 * 15.0,
+                fire_rate: 1.5 + tier as f64 * 0.5,
+                damage: 10.0 + tier as f64 * 5.0,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The cost of building it, then of each upgrade in turn.
+    fn costs(self) -> &'static [u32] {
+        match self {
+            Structure::Wall => &[5],
+            Structure::Tower => &[50, 40, 70],
+            Structure::Barracks => &[80],
+        }
+    }
+
+    /// A new player-owned actor for this structure, with the top left of its
+    /// footprint at `corner`.
+    pub fn build(self, corner: (usize, usize), map: &Map) -> Actor {
+        let ai = match self {
+            Structure::Wall => ActorAi::Inert,
+            Structure::Tower => ActorAi::Tower(TargetPolicy::First),
+            Structure::Barracks => ActorAi::Spawner {
+                rate: 5.0,
+                ai: Box::new(ActorAi::Wanderer),
+            },
+        };
+        let (x, y) = map.footprint_center(corner, self.size());
+        let mut actor = Actor::new(x, y, ActorBody::Building(self, 0), ai);
+        actor.faction = Faction::Player;
+        actor.corner = Some(corner);
+        // Buildings stay wherever they're put.
+        actor.speed = None;
+        actor
+    }
+}
+
+impl Blueprint {
+    /// Lay `structure` out as close to centred on (x, y) as the grid allows.
+    /// Returns `None` if it wouldn't fit on the map there at all.
+    pub fn new(
+        structure: Structure,
+        x: f64,
+        y: f64,
+        map: &Map,
+        qt: &QuadTree<ActorRef>,
+        ledger: &Ledger,
+    ) -> Option<Self> {
+        let (width, height) = structure.size();
+        let corner = map.footprint_at(x, y, width, height)?;
+        let mut blueprint = Self {
+            structure,
+            corner,
+            problem: None,
+        };
+        let cells = blueprint.cells();
+        blueprint.problem = if !cells.iter().all(|&(x, y)| map.is_buildable(x, y)) {
+            Some(Problem::Blocked)
+        } else if !qt.query(&blueprint.region(map)).is_empty() {
+            Some(Problem::Occupied)
+        } else if map.cuts_off_goal(&cells) {
+            Some(Problem::CutsOffGoal)
+        } else {
+            ledger
+                .shortfall(structure.cost())
+                .map(|short| Problem::TooExpensive { short })
+        };
+        Some(blueprint)
+    }
+
+    /// Every cell the structure would cover.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        Map::cells_in(self.corner, self.structure.size())
+    }
+
+    /// The ground the structure would cover, in pixels.
+    fn region(&self, map: &Map) -> Region {
+        let (width, height) = self.structure.size();
+        let (left, top) = map.get_cell_loc(self.corner.0, self.corner.1);
+        let (right, bottom) = map.get_cell_loc(self.corner.0 + width, self.corner.1 + height);
+        Region::new_rect(left, top, right - left, bottom - top)
+    }
+
+    /// A ghost of the footprint, green if it can go here and red if not.
+    pub fn render<G: Graphics>(&self, map: &Map, t: Matrix2d, g: &mut G) {
+        let color = match self.problem {
+            None => [0.2, 0.9, 0.2, 0.5],
+            Some(_) => [0.9, 0.2, 0.2, 0.5],
+        };
+        for (x, y) in self.cells() {
+            map.render_cell(x, y, color, t, g);
+        }
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Structure::Wall => write!(f, "wall"),
+            Structure::Tower => write!(f, "tower"),
+            Structure::Barracks => write!(f, "barracks"),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Blocked => write!(f, "the ground here isn't clear"),
+            Problem::Occupied => write!(f, "somebody is in the way"),
+            Problem::CutsOffGoal => write!(f, "it would cut the road off"),
+            Problem::TooExpensive { short } => write!(f, "you need {} more money", short),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Instant;
+
+use crate::world::*;
+
+/// What sort of harm is being done.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DamageKind {
+    Blast,
+    Burn,
+    Shot,
+}
+
+/// Who did the damage. Kept as a copy so it still makes sense once the
+/// attacker is gone too.
+#[derive(Debug, Clone)]
+pub struct DamageSource {
+    pub name: Option<String>,
+    pub faction: Faction,
+    /// Which actor it was, to find them again if they're still around.
+    pub id: Instant,
+}
+
+/// Harm done to one actor.
+#[derive(Debug, Clone)]
+pub struct Damage {
+    pub amount: f64,
+    pub kind: DamageKind,
+    pub source: Option<DamageSource>,
+}
+
+/// How an actor died, and who was responsible.
+#[derive(Debug, Clone)]
+pub struct Death {
+    pub cause: DamageKind,
+    pub killer: Option<DamageSource>,
+}
+
+impl DamageSource {
+    pub fn new(i: usize, actors: &[Actor]) -> Self {
+        Self {
+            name: actors[i].name.clone(),
+            faction: actors[i].faction,
+            id: actors[i].id,
+        }
+    }
+}
+
+impl Damage {
+    pub fn new(amount: f64, kind: DamageKind, source: Option<DamageSource>) -> Self {
+        Self {
+            amount,
+            kind,
+            source,
+        }
+    }
+
+    /// `amount` scaled down the further `distance` is from the centre of an
+    /// effect with the given `radius`, reaching nothing at the edge.
+    pub fn falloff(amount: f64, distance: f64, radius: f64) -> f64 {
+        amount * (1.0 - distance / radius).clamp(0.0, 1.0)
+    }
+}
+
+impl fmt::Display for DamageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DamageKind::Blast => write!(f, "blast"),
+            DamageKind::Burn => write!(f, "burn"),
+            DamageKind::Shot => write!(f, "shot"),
+        }
+    }
+}
+
+impl fmt::Display for Death {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.killer {
+            Some(killer) => write!(
+                f,
+                "Killed by a {} from {} ({})",
+                self.cause,
+                killer.name.as_deref().unwrap_or("someone"),
+                killer.faction
+            ),
+            None => write!(f, "Killed by a {}", self.cause),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falloff() {
+        assert_eq!(Damage::falloff(40.0, 0.0, 20.0), 40.0);
+        assert_eq!(Damage::falloff(40.0, 10.0, 20.0), 20.0);
+        assert_eq!(Damage::falloff(40.0, 20.0, 20.0), 0.0);
+        // Nothing beyond the edge, rather than negative damage.
+        assert_eq!(Damage::falloff(40.0, 30.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn take_damage() {
+        let mut actor = Actor::new(0.0, 0.0, ActorBody::Worker, ActorAi::Wanderer);
+        let max_hp = actor.hp;
+        let source = DamageSource {
+            name: Some("Bob".into()),
+            faction: Faction::Raiders,
+            id: Instant::now(),
+        };
+        assert!(!actor.take_damage(Damage::new(1.0, DamageKind::Burn, None)));
+        assert_eq!(actor.hp, max_hp - 1.0);
+        assert!(actor.death.is_none());
+        assert!(actor.take_damage(Damage::new(max_hp * 2.0, DamageKind::Blast, Some(source))));
+        assert_eq!(actor.hp, 0.0);
+        let death = actor.death.clone().unwrap();
+        assert_eq!(death.cause, DamageKind::Blast);
+        let killer = death.killer.unwrap();
+        assert_eq!(killer.name.as_deref(), Some("Bob"));
+        assert_eq!(killer.faction, Faction::Raiders);
+        // Dying again doesn't count, or change how it happened.
+        assert!(!actor.take_damage(Damage::new(5.0, DamageKind::Shot, None)));
+        assert_eq!(actor.death.unwrap().cause, DamageKind::Blast);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::world::map::Map;
+use crate::world::wave::Waves;
+use crate::world::*;
+
+/// How many invaders can get through before the game is lost.
+const STARTING_LIVES: u32 = 10;
+
+/// What the player gets paid as each wave starts.
+const WAVE_INCOME: u32 = 25;
+
+/// How a game of tower defense ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+}
+
+/// The state of a game of tower defense: waves of invaders come in along the
+/// roads, and every one that reaches the goal costs a life.
+pub struct Defense {
+    pub lives: u32,
+    waves: Waves,
+    /// How many waves the player has been paid for.
+    paid_for: usize,
+    pub outcome: Option<Outcome>,
+}
+
+/// What changed over one tick of the game.
+pub struct DefenseResults {
+    /// Invaders that got through to the goal, which need removing.
+    pub leaked: Vec<usize>,
+    /// Invaders that have just arrived at the spawn points.
+    pub new_invaders: Vec<Actor>,
+    /// What the player has earned from waves starting.
+    pub income: u32,
+}
+
+impl Defense {
+    pub fn new(waves: Waves) -> Self {
+        Self {
+            lives: STARTING_LIVES,
+            waves,
+            paid_for: 0,
+            outcome: None,
+        }
+    }
+
+    /// Take a life for every invader that has made it to the goal, and send
+    /// in whoever is due from the waves.
+    pub fn update(&mut self, dt: f64, actors: &[Actor], map: &Map) -> DefenseResults {
+        let leaked: Vec<usize> = actors
+            .iter()
+            .enumerate()
+            .filter(|(_, actor)| matches!(actor.ai, ActorAi::Invader))
+            .filter(|(_, actor)| actor.death.is_none())
+            .filter(|(_, actor)| map.goal.is_some() && map.cell_at(actor.x, actor.y) == map.goal)
+            .map(|(index, _)| index)
+            .collect();
+        let mut results = DefenseResults {
+            leaked,
+            new_invaders: vec![],
+            income: 0,
+        };
+        if self.outcome.is_some() {
+            return results;
+        }
+        self.lives = self.lives.saturating_sub(results.leaked.len() as u32);
+        if self.lives == 0 {
+            self.outcome = Some(Outcome::Lost);
+            return results;
+        }
+        results.new_invaders = self.waves.update(dt, map);
+        // Waves called early get paid for here too.
+        results.income = (self.waves.sent() - self.paid_for) as u32 * WAVE_INCOME;
+        self.paid_for = self.waves.sent();
+        let invaders_left = actors.iter().enumerate().any(|(index, actor)| {
+            matches!(actor.ai, ActorAi::Invader)
+                && actor.death.is_none()
+                && !results.leaked.contains(&index)
+        });
+        if self.waves.finished() && results.new_invaders.is_empty() && !invaders_left {
+            self.outcome = Some(Outcome::Won);
+        }
+        results
+    }
+
+    /// Send the next wave now instead of waiting for the countdown.
+    pub fn call_wave_early(&mut self) {
+        if self.outcome.is_none() {
+            self.waves.call_early();
+        }
+    }
+
+    pub fn description(&self) -> String {
+        format!("Lives: {}\n{}", self.lives, self.waves.description())
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Won => write!(f, "ALL WAVES CLEARED"),
+            Outcome::Lost => write!(f, "GAME OVER"),
+        }
+    }
+}
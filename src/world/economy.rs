@@ -0,0 +1,122 @@
+use std::time::Instant;
+
+use crate::world::{Actor, Faction};
+
+/// What the player starts off with.
+const STARTING_BALANCE: u32 = 120;
+
+/// How much money the player has to spend.
+#[derive(Debug)]
+pub struct Ledger {
+    balance: u32,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self {
+            balance: STARTING_BALANCE,
+        }
+    }
+
+    /// How much more the player needs before they can pay `cost`, if any.
+    pub fn shortfall(&self, cost: u32) -> Option<u32> {
+        cost.checked_sub(self.balance).filter(|&short| short > 0)
+    }
+
+    /// Pay `cost`, as long as there's enough to cover it. Otherwise returns
+    /// how much more is needed, and nothing is taken.
+    pub fn spend(&mut self, cost: u32) -> Result<(), u32> {
+        match self.shortfall(cost) {
+            Some(short) => Err(short),
+            None => {
+                self.balance -= cost;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.balance += amount;
+    }
+
+    /// Pay out the bounty on `victim` if the player killed it, and return who
+    /// should be credited with the kill. Nobody gets credit for killing
+    /// themselves.
+    pub fn pay_bounty(&mut self, victim: &Actor) -> Option<Instant> {
+        let killer = victim.death.as_ref()?.killer.as_ref()?;
+        if killer.id == victim.id {
+            return None;
+        }
+        if killer.faction == Faction::Player {
+            self.earn(victim.bounty);
+        }
+        Some(killer.id)
+    }
+
+    pub fn description(&self) -> String {
+        format!("Money: {}", self.balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ActorAi, ActorBody, DamageKind, DamageSource, Death};
+    use std::time::Duration;
+
+    fn killed_by(victim: &mut Actor, faction: Faction, id: Instant) {
+        victim.death = Some(Death {
+            cause: DamageKind::Blast,
+            killer: Some(DamageSource {
+                name: None,
+                faction,
+                id,
+            }),
+        });
+    }
+
+    #[test]
+    fn spending() {
+        let mut ledger = Ledger::new();
+        // Exactly enough is enough.
+        assert_eq!(ledger.shortfall(STARTING_BALANCE), None);
+        assert_eq!(ledger.spend(STARTING_BALANCE - 20), Ok(()));
+        assert_eq!(ledger.shortfall(50), Some(30));
+        // Being short takes nothing.
+        assert_eq!(ledger.spend(50), Err(30));
+        assert_eq!(ledger.description(), "Money: 20");
+        assert_eq!(ledger.spend(20), Ok(()));
+        assert_eq!(ledger.shortfall(0), None);
+    }
+
+    #[test]
+    fn refunds() {
+        let mut ledger = Ledger::new();
+        ledger.spend(100).unwrap();
+        ledger.earn(60);
+        assert_eq!(ledger.description(), "Money: 80");
+        assert_eq!(ledger.shortfall(80), None);
+        assert_eq!(ledger.shortfall(81), Some(1));
+    }
+
+    #[test]
+    fn bounties() {
+        let mut ledger = Ledger::new();
+        let mut victim = Actor::new(0.0, 0.0, ActorBody::Worker, ActorAi::Wanderer);
+        victim.bounty = 10;
+        let killer = victim.id + Duration::from_secs(1);
+        killed_by(&mut victim, Faction::Raiders, killer);
+        assert_eq!(ledger.pay_bounty(&victim), Some(killer));
+        assert_eq!(ledger.description(), "Money: 120");
+        killed_by(&mut victim, Faction::Player, killer);
+        assert_eq!(ledger.pay_bounty(&victim), Some(killer));
+        assert_eq!(ledger.description(), "Money: 130");
+        // Blowing yourself up earns nothing.
+        let id = victim.id;
+        killed_by(&mut victim, Faction::Player, id);
+        assert_eq!(ledger.pay_bounty(&victim), None);
+        assert_eq!(ledger.description(), "Money: 130");
+        victim.death = None;
+        assert_eq!(ledger.pay_bounty(&victim), None);
+    }
+}
@@ -0,0 +1,362 @@
+use graphics::math::Matrix2d;
+use graphics::*;
+
+use crate::anim::AnimationType;
+use crate::debug::render_region;
+use crate::world::*;
+
+/// How long a bomber caught in someone else's blast takes to go off.
+const FUSE_TIME: f64 = 0.5;
+
+/// How long anyone who survives a blast is left reeling.
+const BLAST_STUN: f64 = 0.75;
+
+/// The quickest a lasting effect can pulse.
+const MIN_INTERVAL: f64 = 0.05;
+
+/// What an area effect does to each actor caught in it, every time it pulses.
+#[derive(Debug, Clone)]
+pub enum Payload {
+    /// Hurt everyone inside. With `falloff`, the damage drops off towards the
+    /// edge of the region.
+    Damage {
+        amount: f64,
+        kind: DamageKind,
+        falloff: bool,
+    },
+    /// Put everyone under a status for `duration` seconds.
+    Status {
+        kind: StatusKind,
+        strength: f64,
+        duration: f64,
+    },
+    /// Shove everyone who can move `distance` away from the middle of the
+    /// region.
+    Push { distance: f64 },
+}
+
+/// Something that happens to every actor in a region, either once or pulsing
+/// every `interval` seconds until `duration` runs out. If something caused the
+/// effect, only actors hostile to it are touched.
+#[derive(Debug, Clone)]
+pub struct AreaEffect {
+    pub region: Region,
+    pub payload: Payload,
+    pub source: Option<DamageSource>,
+    duration: f64,
+    interval: f64,
+    /// Seconds since the effect started.
+    time: f64,
+    pulses: u32,
+    animation: Option<AnimationType>,
+}
+
+/// What happened while the effects were running.
+pub struct EffectResults {
+    pub dead_actors: Vec<usize>,
+    pub new_animations: Vec<Animation>,
+}
+
+impl AreaEffect {
+    /// An effect that goes off once, straight away.
+    pub fn new(region: Region, payload: Payload, source: Option<DamageSource>) -> Self {
+        Self {
+            region,
+            payload,
+            source,
+            duration: 0.0,
+            interval: 1.0,
+            time: 0.0,
+            pulses: 0,
+            animation: None,
+        }
+    }
+
+    /// An explosion centred on (x, y), blamed on `source`.
+    pub fn explosion(x: f64, y: f64, radius: f64, damage: f64, source: DamageSource) -> Self {
+        AreaEffect::new(
+            Region::new_circle(x, y, radius),
+            Payload::Damage {
+                amount: damage,
+                kind: DamageKind::Blast,
+                falloff: true,
+            },
+            Some(source),
+        )
+        .animated(AnimationType::Explosion)
+    }
+
+    /// Keep pulsing every `interval` seconds for `duration` seconds. Shorter
+    /// intervals than `MIN_INTERVAL` are raised to it, or the effect would
+    /// never be done pulsing.
+    pub fn lasting(mut self, duration: f64, interval: f64) -> Self {
+        self.duration = duration;
+        self.interval = interval.max(MIN_INTERVAL);
+        self
+    }
+
+    /// Play an animation over the region when the effect starts.
+    pub fn animated(mut self, animation: AnimationType) -> Self {
+        self.animation = Some(animation);
+        self
+    }
+
+    /// Run every effect for one tick. Effects run in the order they were
+    /// created and catch actors in index order, so the outcome of a tick only
+    /// depends on what was there at the start of it. Bombers set off along the
+    /// way light their fuses, and go off in a later tick.
+    pub fn update_all(
+        effects: &mut Vec<AreaEffect>,
+        actors: &mut [Actor],
+        ctx: &TaskContext,
+    ) -> EffectResults {
+        let mut results = EffectResults {
+            dead_actors: vec![],
+            new_animations: vec![],
+        };
+        for effect in effects.iter_mut() {
+            effect.time += ctx.dt;
+            while effect.pulses < effect.pulses_due() {
+                if effect.pulses == 0 {
+                    if let Some(animation) = effect.animation {
+                        let (x, y) = effect.region.center();
+                        results.new_animations.push(Animation::new(
+                            animation,
+                            x,
+                            y,
+                            effect.radius(),
+                        ));
+                    }
+                }
+                effect.pulse(actors, ctx, &mut results);
+                effect.pulses += 1;
+            }
+        }
+        effects.retain(|effect| !effect.finished());
+        results
+    }
+
+    pub fn render<G: Graphics>(&self, t: Matrix2d, g: &mut G) {
+        let color = match &self.payload {
+            Payload::Damage { .. } => [0.9, 0.3, 0.0, 0.2],
+            Payload::Status { kind, .. } => {
+                let [r, g, b, _] = kind.color();
+                [r, g, b, 0.2]
+            }
+            Payload::Push { .. } => [0.9, 0.9, 0.9, 0.2],
+        };
+        render_region(&self.region, color, t, g);
+    }
+
+    /// How many times the effect should have pulsed by now.
+    fn pulses_due(&self) -> u32 {
+        let total = (self.duration / self.interval).floor() as u32 + 1;
+        let so_far = (self.time / self.interval).floor() as u32 + 1;
+        so_far.min(total)
+    }
+
+    fn finished(&self) -> bool {
+        self.time >= self.duration && self.pulses >= self.pulses_due()
+    }
+
+    fn radius(&self) -> f64 {
+        let bounds = self.region.bounding_box();
+        bounds.w().max(bounds.h()) / 2.0
+    }
+
+    fn pulse(&self, actors: &mut [Actor], ctx: &TaskContext, results: &mut EffectResults) {
+        let mut caught: Vec<usize> = ctx
+            .qt
+            .query(&self.region)
+            .into_iter()
+            .map(|actor_ref| actor_ref.id)
+            .collect();
+        caught.sort_unstable();
+        let (x, y) = self.region.center();
+        for index in caught {
+            if actors[index].death.is_some() {
+                continue;
+            }
+            if let Payload::Damage {
+                kind: DamageKind::Blast,
+                ..
+            } = self.payload
+            {
+                // Bombers don't take kindly to being blown up, whoever did it,
+                // and go off in turn once their fuse burns down.
+                if actors[index].ai.is_dangerous() {
+                    let lit = matches!(actors[index].tasks.front(), Some(Task::Explode(_)));
+                    if !lit {
+                        actors[index].order(Task::explode_after(FUSE_TIME), false);
+                    }
+                    continue;
+                }
+            }
+            if let Some(source) = &self.source {
+                if !ctx
+                    .relations
+                    .is_hostile(source.faction, actors[index].faction)
+                {
+                    continue;
+                }
+            }
+            let (ax, ay) = actors[index].get_pos();
+            let distance = vector::mag(ax - x, ay - y);
+            match self.payload {
+                Payload::Damage {
+                    amount,
+                    kind,
+                    falloff,
+                } => {
+                    let amount = if falloff {
+                        Damage::falloff(amount, distance, self.radius())
+                    } else {
+                        amount
+                    };
+                    if actors[index].take_damage(Damage::new(amount, kind, self.source.clone())) {
+                        results.dead_actors.push(index);
+                    } else if kind == DamageKind::Blast {
+                        actors[index].statuses.apply(Status::new(
+                            StatusKind::Stun,
+                            1.0,
+                            BLAST_STUN,
+                            self.source.clone(),
+                        ));
+                    }
+                }
+                Payload::Status {
+                    kind,
+                    strength,
+                    duration,
+                } => {
+                    actors[index].statuses.apply(Status::new(
+                        kind,
+                        strength,
+                        duration,
+                        self.source.clone(),
+                    ));
+                }
+                Payload::Push { distance: push } => {
+                    let (dx, dy) = vector::direction(x, y, ax, ay);
+                    if actors[index].can_move() && !dx.is_nan() && !dy.is_nan() {
+                        let (dx, dy) = vector::scale(dx, dy, push);
+                        actors[index].x += dx;
+                        actors[index].y += dy;
+                        actors[index].constrain_location(ctx.bounds);
+                        actors[index].keep_off_buildings(ax, ay, ctx.map);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map::Map;
+
+    fn actor(x: f64, y: f64, ai: ActorAi, faction: Faction) -> Actor {
+        let mut actor = Actor::new(x, y, ActorBody::Worker, ai);
+        actor.faction = faction;
+        actor
+    }
+
+    fn blast(x: f64, y: f64, r: f64, amount: f64, kind: DamageKind, falloff: bool) -> AreaEffect {
+        let payload = Payload::Damage {
+            amount,
+            kind,
+            falloff,
+        };
+        AreaEffect::new(Region::new_circle(x, y, r), payload, None)
+    }
+
+    /// Run the actors and effects for `ticks` ticks, leaving the dead where
+    /// they are, and say how everyone ended up.
+    fn run(
+        mut actors: Vec<Actor>,
+        mut effects: Vec<AreaEffect>,
+        ticks: usize,
+    ) -> Vec<(f64, Option<DamageKind>)> {
+        let map = Map::new(50, 50);
+        let bounds = map.get_bounds();
+        let scripts = Scripts::empty();
+        let relations = Relations::default();
+        for _ in 0..ticks {
+            let mut qt = QuadTree::new(RectangleData::new(bounds.x, bounds.y, bounds.w, bounds.h));
+            for (i, actor) in actors.iter().enumerate() {
+                qt.insert(actor.get_ref(i));
+            }
+            let ctx = TaskContext {
+                dt: 0.1,
+                qt: &qt,
+                bounds: &bounds,
+                map: &map,
+                time_of_day: 0.5,
+                scripts: &scripts,
+                relations: &relations,
+            };
+            AreaEffect::update_all(&mut effects, &mut actors, &ctx);
+            let results = Actor::update_all(&mut actors, &ctx);
+            effects.extend(results.new_effects);
+        }
+        actors
+            .iter()
+            .map(|actor| (actor.hp, actor.death.as_ref().map(|death| death.cause)))
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_effects() {
+        let scenario = || {
+            let actors = vec![
+                actor(100.0, 100.0, ActorAi::Inert, Faction::Wild),
+                actor(140.0, 100.0, ActorAi::Inert, Faction::Wild),
+            ];
+            let effects = vec![
+                blast(100.0, 100.0, 30.0, 30.0, DamageKind::Burn, false),
+                blast(120.0, 100.0, 30.0, 25.0, DamageKind::Blast, false),
+            ];
+            run(actors, effects, 1)
+        };
+        let outcome = scenario();
+        // Caught by both, but the first effect to run is what killed it.
+        assert_eq!(outcome[0], (0.0, Some(DamageKind::Burn)));
+        assert_eq!(outcome[1], (5.0, None));
+        assert_eq!(outcome, scenario());
+    }
+
+    #[test]
+    fn bombers_chain() {
+        let scenario = |ticks| {
+            let actors = vec![
+                actor(100.0, 100.0, ActorAi::Kamikaze, Faction::Raiders),
+                actor(110.0, 100.0, ActorAi::Inert, Faction::Wild),
+                actor(300.0, 300.0, ActorAi::Inert, Faction::Wild),
+            ];
+            let effects = vec![blast(90.0, 100.0, 15.0, 5.0, DamageKind::Blast, true)];
+            run(actors, effects, ticks)
+        };
+        let (lit, done) = (scenario(3), scenario(10));
+        // The bomber shrugs off the blast that caught it, and waits on its
+        // fuse.
+        assert_eq!(lit[0], (30.0, None));
+        assert_eq!(lit[1], (30.0, None));
+        assert_eq!(done[0], (0.0, Some(DamageKind::Blast)));
+        assert_eq!(done[1], (0.0, Some(DamageKind::Blast)));
+        assert_eq!(done[2], (30.0, None));
+        assert_eq!(done, scenario(10));
+    }
+
+    #[test]
+    fn short_intervals_are_raised() {
+        for &interval in [0.0, -1.0, f64::NAN].iter() {
+            let mut effect =
+                blast(0.0, 0.0, 1.0, 1.0, DamageKind::Burn, false).lasting(1.0, interval);
+            assert_eq!(effect.interval, MIN_INTERVAL);
+            effect.time = 1.0;
+            effect.pulses = effect.pulses_due();
+            assert!(effect.finished());
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use graphics::types::Color;
+use graphics::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::world::*;
+
+/// Which side an actor is on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Faction {
+    Player,
+    Wild,
+    Raiders,
+}
+
+/// How one faction feels about another.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Relation {
+    Ally,
+    Neutral,
+    Hostile,
+}
+
+/// How every faction feels about every other. Any pair not listed is
+/// neutral, and every faction is allied with itself.
+pub struct Relations {
+    table: HashMap<(Faction, Faction), Relation>,
+}
+
+impl Faction {
+    const ALL: [Faction; 3] = [Faction::Player, Faction::Wild, Faction::Raiders];
+
+    pub fn color(self) -> Color {
+        match self {
+            Faction::Player => color::hex("66ccff"),
+            Faction::Wild => color::hex("ffffff"),
+            Faction::Raiders => color::hex("ff6644"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Faction::Player => "player",
+            Faction::Wild => "wild",
+            Faction::Raiders => "raiders",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Faction> {
+        Faction::ALL
+            .iter()
+            .copied()
+            .find(|faction| faction.name() == name)
+    }
+}
+
+impl Relation {
+    fn from_name(name: &str) -> Option<Relation> {
+        match name {
+            "ally" => Some(Relation::Ally),
+            "neutral" => Some(Relation::Neutral),
+            "hostile" => Some(Relation::Hostile),
+            _ => None,
+        }
+    }
+}
+
+impl Relations {
+    /// Load the table from a file with one `<faction> <faction> <relation>`
+    /// line per pair. Falls back to the defaults if the file can't be read,
+    /// and skips over any lines that don't make sense.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let source = match fs::read_to_string(path.as_ref()) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", path.as_ref().display(), e);
+                return Relations::default();
+            }
+        };
+        let mut relations = Relations {
+            table: HashMap::new(),
+        };
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            let parsed = match words[..] {
+                [a, b, relation] => Faction::from_name(a)
+                    .zip(Faction::from_name(b))
+                    .zip(Relation::from_name(relation)),
+                _ => None,
+            };
+            match parsed {
+                Some(((a, b), relation)) => relations.set(a, b, relation),
+                None => eprintln!(
+                    "{} line {}: expected \"<faction> <faction> <relation>\"",
+                    path.as_ref().display(),
+                    number + 1
+                ),
+            }
+        }
+        relations
+    }
+
+    /// Set how `a` and `b` feel about each other. Relations always go both
+    /// ways.
+    pub fn set(&mut self, a: Faction, b: Faction, relation: Relation) {
+        self.table.insert((a, b), relation);
+        self.table.insert((b, a), relation);
+    }
+
+    pub fn get(&self, a: Faction, b: Faction) -> Relation {
+        match self.table.get(&(a, b)) {
+            Some(&relation) => relation,
+            None if a == b => Relation::Ally,
+            None => Relation::Neutral,
+        }
+    }
+
+    pub fn is_hostile(&self, a: Faction, b: Faction) -> bool {
+        self.get(a, b) == Relation::Hostile
+    }
+}
+
+impl Default for Relations {
+    fn default() -> Self {
+        let mut relations = Relations {
+            table: HashMap::new(),
+        };
+        relations.set(Faction::Player, Faction::Raiders, Relation::Hostile);
+        relations.set(Faction::Wild, Faction::Raiders, Relation::Hostile);
+        relations
+    }
+}
+
+impl fmt::Display for Faction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Faction::Player => "Player",
+                Faction::Wild => "Wild",
+                Faction::Raiders => "Raiders",
+            }
+        )
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Relation::Ally => "ally",
+                Relation::Neutral => "neutral",
+                Relation::Hostile => "hostile",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let relations = Relations::default();
+        for &faction in Faction::ALL.iter() {
+            assert_eq!(relations.get(faction, faction), Relation::Ally);
+        }
+        assert!(relations.is_hostile(Faction::Raiders, Faction::Player));
+        assert!(relations.is_hostile(Faction::Wild, Faction::Raiders));
+        assert_eq!(
+            relations.get(Faction::Player, Faction::Wild),
+            Relation::Neutral
+        );
+    }
+
+    #[test]
+    fn set_goes_both_ways() {
+        let mut relations = Relations::default();
+        relations.set(Faction::Player, Faction::Wild, Relation::Hostile);
+        assert!(relations.is_hostile(Faction::Wild, Faction::Player));
+        relations.set(Faction::Raiders, Faction::Raiders, Relation::Hostile);
+        assert!(relations.is_hostile(Faction::Raiders, Faction::Raiders));
+    }
+
+    #[test]
+    fn load_relations() {
+        let path = std::env::temp_dir().join(format!("factions-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "# comment\nplayer wild ally\n\nwild raiders hostile # trailing\nnonsense\n",
+        )
+        .unwrap();
+        let relations = Relations::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            relations.get(Faction::Wild, Faction::Player),
+            Relation::Ally
+        );
+        assert!(relations.is_hostile(Faction::Raiders, Faction::Wild));
+        // Only what's in the file counts, so this pair is back to neutral.
+        assert_eq!(
+            relations.get(Faction::Player, Faction::Raiders),
+            Relation::Neutral
+        );
+        assert_eq!(
+            relations.get(Faction::Player, Faction::Player),
+            Relation::Ally
+        );
+    }
+
+    #[test]
+    fn missing_file_falls_back_to_defaults() {
+        let relations = Relations::load("no/such/factions.txt");
+        assert!(relations.is_hostile(Faction::Player, Faction::Raiders));
+    }
+}
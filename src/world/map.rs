@@ -3,8 +3,11 @@ use graphics::types::Color;
 use graphics::*;
 use rand::Rng;
 
-const GRID_TILE_SIZE: f64 = 12.0;
+use crate::world::{Actor, Faction};
 
+pub const GRID_TILE_SIZE: f64 = 12.0;
+
+#[derive(Clone, Copy)]
 pub enum Tile {
     Grass,
     Forest,
@@ -13,6 +16,28 @@ pub enum Tile {
     Gold,
     Road,
     Building,
+    /// Where invaders are trying to get to.
+    Goal,
+}
+
+impl Tile {
+    /// Whether this tile blocks line of sight.
+    pub fn is_opaque(self) -> bool {
+        match self {
+            Tile::Forest | Tile::Stone | Tile::Building => true,
+            Tile::Grass | Tile::Ore | Tile::Gold | Tile::Road | Tile::Goal => false,
+        }
+    }
+
+    /// Whether invaders can walk along this tile.
+    pub fn is_road(self) -> bool {
+        matches!(self, Tile::Road | Tile::Goal)
+    }
+
+    /// Whether anything can stand on this tile, or be built on it.
+    pub fn is_passable(self) -> bool {
+        !matches!(self, Tile::Forest | Tile::Stone | Tile::Building)
+    }
 }
 
 pub struct WorldBounds {
@@ -46,10 +71,27 @@ impl WorldBounds {
     }
 }
 
+/// How much the player knows about a cell.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Visibility {
+    NeverSeen,
+    /// Seen at some point, but nobody is looking at it right now.
+    Explored,
+    Visible,
+}
+
 pub struct Map {
     grid: Vec<Tile>,
+    visibility: Vec<Visibility>,
+    /// Which cells have something built on them.
+    occupied: Vec<bool>,
+    /// Whether to hide what the player's actors can't see.
+    pub fog: bool,
     width: usize,
     height: usize,
+    /// Cells on the edge of the map where invaders come in.
+    pub spawn_points: Vec<(usize, usize)>,
+    pub goal: Option<(usize, usize)>,
 }
 
 impl Map {
@@ -67,8 +109,255 @@ impl Map {
             .collect();
         Self {
             grid,
+            visibility: vec![Visibility::NeverSeen; width * height],
+            occupied: vec![false; width * height],
+            fog: true,
             width,
             height,
+            spawn_points: vec![],
+            goal: None,
+        }
+    }
+
+    /// A map with a goal in the middle, and a road leading to it from each
+    /// of the spawn points around the edge. Any other road is torn up so it
+    /// can't offer a shortcut.
+    pub fn with_roads(width: usize, height: usize) -> Self {
+        let mut map = Map::new(width, height);
+        for tile in map.grid.iter_mut() {
+            if let Tile::Road = tile {
+                *tile = Tile::Grass;
+            }
+        }
+        let goal = (width / 2, height / 2);
+        map.spawn_points = vec![(0, height / 4), (width - 1, height * 3 / 4), (width / 3, 0)];
+        for &spawn in map.spawn_points.clone().iter() {
+            map.lay_road(spawn, goal);
+        }
+        let index = map.get_index(goal.0, goal.1);
+        map.grid[index] = Tile::Goal;
+        map.goal = Some(goal);
+        map
+    }
+
+    /// Wander a road from `from` to `to`, one cell at a time, always getting
+    /// closer but picking at random which way.
+    fn lay_road(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let (mut x, mut y) = from;
+        loop {
+            let index = self.get_index(x, y);
+            self.grid[index] = Tile::Road;
+            if (x, y) == to {
+                break;
+            }
+            let horizontal = if x == to.0 {
+                false
+            } else if y == to.1 {
+                true
+            } else {
+                rand::thread_rng().gen_bool(0.5)
+            };
+            if horizontal {
+                x = if x < to.0 { x + 1 } else { x - 1 };
+            } else {
+                y = if y < to.1 { y + 1 } else { y - 1 };
+            }
+        }
+    }
+
+    /// The shortest way from one cell to another along the roads, including
+    /// both ends, if there is one. Anything built on the road is in the way.
+    pub fn road_path(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<Vec<(usize, usize)>> {
+        self.road_path_avoiding(from, to, &[])
+    }
+
+    /// Whether building over `cells` would leave any of the spawn points with
+    /// no way along the roads to the goal.
+    pub fn cuts_off_goal(&self, cells: &[(usize, usize)]) -> bool {
+        let goal = match self.goal {
+            Some(goal) => goal,
+            None => return false,
+        };
+        self.spawn_points.iter().any(|&spawn| {
+            self.road_path(spawn, goal).is_some()
+                && self.road_path_avoiding(spawn, goal, cells).is_none()
+        })
+    }
+
+    /// Whether there's room to build on a cell.
+    pub fn is_buildable(&self, x: usize, y: usize) -> bool {
+        let index = self.get_index(x, y);
+        self.grid[index].is_passable() && !self.occupied[index]
+    }
+
+    /// Whether the cell under (x, y) has something built on it.
+    pub fn is_built_on(&self, x: f64, y: f64) -> bool {
+        self.get_cell_for_pixel(x, y)
+            .is_some_and(|(x, y)| self.occupied[self.get_index(x, y)])
+    }
+
+    /// Mark `cells` as built on, or clear them again.
+    pub fn occupy(&mut self, cells: &[(usize, usize)], occupied: bool) {
+        for &(x, y) in cells.iter() {
+            let index = self.get_index(x, y);
+            self.occupied[index] = occupied;
+        }
+    }
+
+    /// The `width` by `height` block of cells roughly centred on (x, y),
+    /// given by its top left corner, if it fits on the map.
+    pub fn footprint_at(
+        &self,
+        x: f64,
+        y: f64,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        if !self.get_bounds().in_bounds(x, y) {
+            return None;
+        }
+        let left = (x / GRID_TILE_SIZE - width as f64 / 2.0).round();
+        let top = (y / GRID_TILE_SIZE - height as f64 / 2.0).round();
+        if left < 0.0 || top < 0.0 {
+            return None;
+        }
+        let (left, top) = (left as usize, top as usize);
+        if left + width > self.width || top + height > self.height {
+            return None;
+        }
+        Some((left, top))
+    }
+
+    /// Every cell in the `width` by `height` block with its top left corner at
+    /// `corner`.
+    pub fn cells_in(
+        corner: (usize, usize),
+        (width, height): (usize, usize),
+    ) -> Vec<(usize, usize)> {
+        let (left, top) = corner;
+        (top..top + height)
+            .flat_map(|y| (left..left + width).map(move |x| (x, y)))
+            .collect()
+    }
+
+    /// The middle of the `width` by `height` block with its top left corner at
+    /// `corner`.
+    pub fn footprint_center(
+        &self,
+        corner: (usize, usize),
+        (width, height): (usize, usize),
+    ) -> (f64, f64) {
+        let (left, top) = self.get_cell_loc(corner.0, corner.1);
+        (
+            left + width as f64 * GRID_TILE_SIZE / 2.0,
+            top + height as f64 * GRID_TILE_SIZE / 2.0,
+        )
+    }
+
+    fn road_path_avoiding(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        avoid: &[(usize, usize)],
+    ) -> Option<Vec<(usize, usize)>> {
+        let open = |(x, y): (usize, usize)| {
+            let index = self.get_index(x, y);
+            self.grid[index].is_road() && !self.occupied[index] && !avoid.contains(&(x, y))
+        };
+        if !open(from) || !open(to) {
+            return None;
+        }
+        // A breadth-first search, remembering where each cell was reached
+        // from so the path can be walked back afterwards.
+        let mut came_from: Vec<Option<(usize, usize)>> = vec![None; self.grid.len()];
+        let mut queue = std::collections::VecDeque::new();
+        came_from[self.get_index(from.0, from.1)] = Some(from);
+        queue.push_back(from);
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) == to {
+                let mut path = vec![to];
+                let mut cell = to;
+                while cell != from {
+                    cell = came_from[self.get_index(cell.0, cell.1)].unwrap();
+                    path.push(cell);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for &(nx, ny) in neighbours.iter() {
+                if nx >= self.width || ny >= self.height || !open((nx, ny)) {
+                    continue;
+                }
+                let index = self.get_index(nx, ny);
+                if came_from[index].is_none() {
+                    came_from[index] = Some((x, y));
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        None
+    }
+
+    /// Work out which cells the player's actors can see right now. Anything
+    /// they could see before but can't any more is left explored.
+    pub fn update_visibility(&mut self, actors: &[Actor]) {
+        for cell in self.visibility.iter_mut() {
+            if *cell == Visibility::Visible {
+                *cell = Visibility::Explored;
+            }
+        }
+        for actor in actors
+            .iter()
+            .filter(|actor| actor.faction == Faction::Player)
+        {
+            let range = match actor.get_sight_range() {
+                Some(range) => range.bounding_box(),
+                None => continue,
+            };
+            let (x, y) = actor.get_pos();
+            let bounds = self.get_bounds();
+            let (x1, y1) = bounds.constrain(range.x(), range.y());
+            let (x2, y2) = bounds.constrain(range.x() + range.w(), range.y() + range.h());
+            let ((min_x, min_y), (max_x, max_y)) =
+                match (self.cell_at(x1, y1), self.cell_at(x2, y2)) {
+                    (Some(min), Some(max)) => (min, max),
+                    _ => continue,
+                };
+            for cell_x in min_x..=max_x {
+                for cell_y in min_y..=max_y {
+                    let (cx, cy) = self.cell_center(cell_x, cell_y);
+                    // Always reveal the cell the actor is standing in.
+                    let standing_in = self.cell_at(x, y) == Some((cell_x, cell_y));
+                    if standing_in || (actor.can_see(cx, cy) && self.line_of_sight(x, y, cx, cy)) {
+                        let index = self.get_index(cell_x, cell_y);
+                        self.visibility[index] = Visibility::Visible;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the player can currently see (x,y). Always true with the fog
+    /// turned off.
+    pub fn is_visible(&self, x: f64, y: f64) -> bool {
+        if !self.fog {
+            return true;
+        }
+        match self.cell_at(x, y) {
+            Some((cell_x, cell_y)) => {
+                self.visibility[self.get_index(cell_x, cell_y)] == Visibility::Visible
+            }
+            None => false,
         }
     }
 
@@ -78,11 +367,14 @@ impl Map {
                 self.render_tile(x, y, t, g);
             }
         }
+        // Mark where the invaders come in.
+        for &(x, y) in self.spawn_points.iter() {
+            self.render_cell(x, y, color::hex("aa33aa"), t, g);
+        }
 
         // Highlight the current cell
-        match self.get_cell_for_pixel(mouse_x, mouse_y) {
-            Some((x, y)) => self.render_cell(x, y, [1.0, 1.0, 1.0, 0.3], t, g),
-            _ => {}
+        if let Some((x, y)) = self.get_cell_for_pixel(mouse_x, mouse_y) {
+            self.render_cell(x, y, [1.0, 1.0, 1.0, 0.3], t, g);
         }
     }
 
@@ -95,12 +387,20 @@ impl Map {
             Tile::Gold => color::hex("009999"),
             Tile::Ore => color::hex("00aaaa"),
             Tile::Stone => color::hex("00bbbb"),
+            Tile::Goal => color::hex("cc3333"),
         };
 
         self.render_cell(x, y, color, t, g);
+        if self.fog {
+            match self.visibility[self.get_index(x, y)] {
+                Visibility::NeverSeen => self.render_cell(x, y, [0.0, 0.0, 0.0, 1.0], t, g),
+                Visibility::Explored => self.render_cell(x, y, [0.0, 0.0, 0.0, 0.5], t, g),
+                Visibility::Visible => {}
+            }
+        }
     }
 
-    fn render_cell<G: Graphics>(
+    pub fn render_cell<G: Graphics>(
         &self,
         x_index: usize,
         y_index: usize,
@@ -130,6 +430,74 @@ impl Map {
         }
     }
 
+    /// Whether there's a clear view from (x1,y1) to (x2,y2), walking the
+    /// cells the line passes through and stopping at the first opaque one.
+    /// The cells at either end don't count, so actors can see into (and out
+    /// of) the edge of a forest.
+    pub fn line_of_sight(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
+        let (start, end) = match (self.cell_at(x1, y1), self.cell_at(x2, y2)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return false,
+        };
+        let (mut x, mut y) = (start.0 as isize, start.1 as isize);
+        let (end_x, end_y) = (end.0 as isize, end.1 as isize);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let (step_x, step_y) = (dx.signum() as isize, dy.signum() as isize);
+        // How far along the line, from 0 to 1, it takes to cross a whole
+        // cell, and to reach the next cell boundary, in each direction.
+        let delta_x = GRID_TILE_SIZE / dx.abs();
+        let delta_y = GRID_TILE_SIZE / dy.abs();
+        let mut next_x = match dx {
+            dx if dx > 0.0 => ((x + 1) as f64 * GRID_TILE_SIZE - x1) / dx,
+            dx if dx < 0.0 => (x as f64 * GRID_TILE_SIZE - x1) / dx,
+            _ => f64::INFINITY,
+        };
+        let mut next_y = match dy {
+            dy if dy > 0.0 => ((y + 1) as f64 * GRID_TILE_SIZE - y1) / dy,
+            dy if dy < 0.0 => (y as f64 * GRID_TILE_SIZE - y1) / dy,
+            _ => f64::INFINITY,
+        };
+        // Each step moves one cell either horizontally or vertically, so
+        // this is exactly how many it takes to get to the end.
+        let steps = (end_x - x).abs() + (end_y - y).abs();
+        for _ in 0..steps {
+            if next_x < next_y {
+                next_x += delta_x;
+                x += step_x;
+            } else {
+                next_y += delta_y;
+                y += step_y;
+            }
+            if (x, y) == (end_x, end_y) {
+                break;
+            }
+            if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+                break;
+            }
+            if self.grid[self.get_index(x as usize, y as usize)].is_opaque() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The cell containing (x,y), if it's on the map.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        if !self.get_bounds().in_bounds(x, y) {
+            return None;
+        }
+        // Anything right on the far edge belongs to the last cell.
+        Some((
+            ((x / GRID_TILE_SIZE) as usize).min(self.width - 1),
+            ((y / GRID_TILE_SIZE) as usize).min(self.height - 1),
+        ))
+    }
+
+    pub fn cell_center(&self, x: usize, y: usize) -> (f64, f64) {
+        let (x, y) = self.get_cell_loc(x, y);
+        (x + GRID_TILE_SIZE / 2.0, y + GRID_TILE_SIZE / 2.0)
+    }
+
     pub fn get_cell_loc(&self, x: usize, y: usize) -> (f64, f64) {
         (x as f64 * GRID_TILE_SIZE, y as f64 * GRID_TILE_SIZE)
     }
@@ -143,3 +511,154 @@ impl Map {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ActorAi, ActorBody};
+
+    fn open_map(width: usize, height: usize) -> Map {
+        Map {
+            grid: vec![Tile::Grass; width * height],
+            visibility: vec![Visibility::NeverSeen; width * height],
+            occupied: vec![false; width * height],
+            fog: true,
+            width,
+            height,
+            spawn_points: vec![],
+            goal: None,
+        }
+    }
+
+    fn center(x: usize, y: usize) -> (f64, f64) {
+        (
+            (x as f64 + 0.5) * GRID_TILE_SIZE,
+            (y as f64 + 0.5) * GRID_TILE_SIZE,
+        )
+    }
+
+    fn can_see(map: &Map, from: (usize, usize), to: (usize, usize)) -> bool {
+        let (x1, y1) = center(from.0, from.1);
+        let (x2, y2) = center(to.0, to.1);
+        map.line_of_sight(x1, y1, x2, y2)
+    }
+
+    #[test]
+    fn open_ground() {
+        let map = open_map(10, 10);
+        assert!(can_see(&map, (0, 0), (9, 9)));
+        assert!(can_see(&map, (9, 0), (0, 7)));
+        assert!(can_see(&map, (4, 4), (4, 4)));
+        assert!(can_see(&map, (2, 5), (8, 5)));
+    }
+
+    #[test]
+    fn blocked_by_opaque_tiles() {
+        let mut map = open_map(10, 10);
+        let wall = map.get_index(5, 5);
+        map.grid[wall] = Tile::Building;
+        assert!(!can_see(&map, (2, 5), (8, 5)));
+        assert!(!can_see(&map, (5, 2), (5, 8)));
+        assert!(!can_see(&map, (3, 3), (7, 7)));
+        // Lines that pass by without touching it are fine.
+        assert!(can_see(&map, (2, 4), (8, 4)));
+        assert!(!can_see(&map, (3, 7), (7, 3)));
+        // The cells at either end never block.
+        assert!(can_see(&map, (5, 5), (8, 5)));
+        assert!(can_see(&map, (2, 5), (5, 5)));
+        // Roads don't block anything.
+        map.grid[wall] = Tile::Road;
+        assert!(can_see(&map, (2, 5), (8, 5)));
+    }
+
+    #[test]
+    fn follows_roads() {
+        let mut map = open_map(5, 5);
+        for &(x, y) in [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (3, 2), (4, 2)].iter() {
+            let index = map.get_index(x, y);
+            map.grid[index] = Tile::Road;
+        }
+        let path = map.road_path((0, 0), (4, 2)).unwrap();
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 2)));
+        // Off the road, or cut off from it, there's no way through.
+        assert!(map.road_path((0, 0), (4, 4)).is_none());
+        let gap = map.get_index(2, 1);
+        map.grid[gap] = Tile::Grass;
+        assert!(map.road_path((0, 0), (4, 2)).is_none());
+    }
+
+    #[test]
+    fn building_on_roads() {
+        let mut map = open_map(5, 5);
+        // Two ways round from the spawn point to the goal.
+        for &(x, y) in [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+        ]
+        .iter()
+        {
+            let index = map.get_index(x, y);
+            map.grid[index] = Tile::Road;
+        }
+        map.spawn_points = vec![(0, 0)];
+        map.goal = Some((2, 1));
+        assert!(!map.cuts_off_goal(&[(1, 0)]));
+        assert!(!map.cuts_off_goal(&[(3, 3)]));
+        map.occupy(&[(1, 0)], true);
+        assert!(!map.is_buildable(1, 0));
+        assert_eq!(
+            map.road_path((0, 0), (2, 1)).map(|path| path.len()),
+            Some(6)
+        );
+        assert!(map.cuts_off_goal(&[(1, 2)]));
+        map.occupy(&[(1, 0)], false);
+        assert!(!map.cuts_off_goal(&[(1, 2)]));
+    }
+
+    #[test]
+    fn footprints_snap_to_cells() {
+        let map = open_map(10, 10);
+        let corner = map.footprint_at(3.4 * GRID_TILE_SIZE, 5.0 * GRID_TILE_SIZE, 3, 2);
+        assert_eq!(corner, Some((2, 4)));
+        // Whatever is built there finds its way back to the same cells.
+        let (x, y) = map.footprint_center((2, 4), (3, 2));
+        assert_eq!(map.footprint_at(x, y, 3, 2), Some((2, 4)));
+        // Too close to the edge for it all to fit.
+        assert_eq!(
+            map.footprint_at(9.5 * GRID_TILE_SIZE, 5.0 * GRID_TILE_SIZE, 3, 2),
+            None
+        );
+    }
+
+    #[test]
+    fn buildings_block_movement() {
+        let mut map = open_map(10, 10);
+        map.occupy(&Map::cells_in((4, 4), (2, 2)), true);
+        let (x, y) = center(4, 4);
+        assert!(map.is_built_on(x, y));
+        let mut actor = Actor::new(x - GRID_TILE_SIZE, y, ActorBody::Worker, ActorAi::Wanderer);
+        // Walking into the building stops at its edge.
+        assert!(actor.step_towards(x, y, 10.0, &map));
+        assert!(!map.is_built_on(actor.x, actor.y));
+        // But anyone caught inside can walk back out.
+        actor.x = x;
+        actor.step_towards(x - GRID_TILE_SIZE, y, 10.0, &map);
+        assert_eq!(actor.get_pos(), (x - GRID_TILE_SIZE, y));
+    }
+
+    #[test]
+    fn off_the_map() {
+        let map = open_map(10, 10);
+        assert!(!map.line_of_sight(-1.0, 5.0, 20.0, 20.0));
+        assert!(map.cell_at(10.0 * GRID_TILE_SIZE, 0.0).is_some());
+        assert!(map.cell_at(10.5 * GRID_TILE_SIZE, 0.0).is_none());
+    }
+}
@@ -1,11 +1,27 @@
 use std::fmt;
 
-const ACTOR_REF_SIZE: f64 = 10.0;
+pub const ACTOR_REF_SIZE: f64 = 10.0;
+/// The most any actor can carry at once.
+pub const MAX_LOAD: f64 = 10.0;
 
 pub mod actor;
 pub mod ai;
+pub mod behaviour;
+pub mod build;
+pub mod damage;
+pub mod defense;
+pub mod economy;
+pub mod effect;
+pub mod faction;
 pub mod map;
+pub mod perception;
+pub mod projectile;
+pub mod script;
+pub mod status;
 pub mod task;
+pub mod tower;
+pub mod utility;
+pub mod wave;
 
 mod vector;
 
@@ -13,15 +29,29 @@ use crate::anim::Animation;
 use crate::qt::*;
 pub use actor::*;
 pub use ai::*;
+pub use behaviour::Behaviours;
+pub use build::{Blueprint, Structure};
+pub use damage::{Damage, DamageKind, DamageSource, Death};
+pub use defense::Defense;
+pub use economy::Ledger;
+pub use effect::{AreaEffect, Payload};
+pub use faction::{Faction, Relation, Relations};
+pub use perception::Perception;
+pub use projectile::Projectile;
+pub use script::Scripts;
+pub use status::{Status, StatusKind, Statuses};
 pub use task::*;
+pub use tower::{TargetPolicy, Weapon};
+pub use utility::Utility;
 
 pub struct UpdateResults {
     pub new_actors: Vec<Actor>,
     pub dead_actors: Vec<usize>,
-    pub new_animations: Vec<Animation>,
+    pub new_effects: Vec<AreaEffect>,
+    pub new_projectiles: Vec<Projectile>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct ActorRef {
     pub id: usize,
     region: Region,
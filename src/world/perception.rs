@@ -0,0 +1,143 @@
+use crate::world::*;
+
+/// How long an actor keeps remembering someone after losing sight of them.
+const MEMORY_DURATION: f64 = 10.0;
+
+/// What an actor knows about the actors around it: who it can see right now,
+/// and where it last saw anyone it's lost track of.
+#[derive(Debug, Clone, Default)]
+pub struct Perception {
+    visible: Vec<Target>,
+    memory: Vec<Memory>,
+}
+
+/// Where an actor was last seen, and how long ago.
+#[derive(Debug, Clone)]
+pub struct Memory {
+    pub target: Target,
+    pub x: f64,
+    pub y: f64,
+    pub age: f64,
+}
+
+impl Memory {
+    /// How much to trust this memory, from 1 (just seen) down to 0 (about to
+    /// be forgotten).
+    pub fn freshness(&self) -> f64 {
+        (1.0 - self.age / MEMORY_DURATION).clamp(0.0, 1.0)
+    }
+}
+
+impl Perception {
+    /// Work out what every actor can see this tick, and let anything else
+    /// they remember fade a little more.
+    pub fn update_all(actors: &mut [Actor], ctx: &TaskContext) {
+        for i in 0..actors.len() {
+            let visible: Vec<Target> = match actors[i].get_sight_range() {
+                Some(region) => ctx
+                    .qt
+                    .query(&region)
+                    .into_iter()
+                    .map(|actor_ref| actor_ref.id)
+                    .filter(|&id| id != i)
+                    .filter(|&id| {
+                        let (x, y) = actors[id].get_pos();
+                        let (own_x, own_y) = actors[i].get_pos();
+                        actors[i].can_see(x, y) && ctx.map.line_of_sight(own_x, own_y, x, y)
+                    })
+                    .map(|id| Target::new(id, actors[id].id))
+                    .collect(),
+                None => vec![],
+            };
+            let mut memory = std::mem::take(&mut actors[i].perception.memory);
+            for remembered in memory.iter_mut() {
+                remembered.age += ctx.dt;
+            }
+            for target in visible.iter() {
+                let (x, y) = target.index.map(|index| actors[index].get_pos()).unwrap();
+                match memory.iter_mut().find(|m| m.target.id == target.id) {
+                    Some(remembered) => {
+                        remembered.target = target.clone();
+                        remembered.x = x;
+                        remembered.y = y;
+                        remembered.age = 0.0;
+                    }
+                    None => memory.push(Memory {
+                        target: target.clone(),
+                        x,
+                        y,
+                        age: 0.0,
+                    }),
+                }
+            }
+            memory.retain(|remembered| remembered.age < MEMORY_DURATION);
+            actors[i].perception = Perception { visible, memory };
+        }
+    }
+
+    /// Point everything back at the right actors once some have been removed,
+    /// forgetting about any that are gone.
+    pub fn fix_targets(&mut self, actors: &[Actor]) {
+        for target in self.visible.iter_mut() {
+            target.fix(actors);
+        }
+        self.visible.retain(|target| target.index.is_some());
+        for remembered in self.memory.iter_mut() {
+            remembered.target.fix(actors);
+        }
+        self.memory
+            .retain(|remembered| remembered.target.index.is_some());
+    }
+
+    /// Everyone in sight right now.
+    pub fn visible(&self) -> impl Iterator<Item = usize> + '_ {
+        self.visible.iter().filter_map(|target| target.index)
+    }
+
+    /// The closest actor in sight to actor `i` that passes `filter`, along
+    /// with how far away it is.
+    pub fn nearest<F>(&self, i: usize, actors: &[Actor], filter: F) -> Option<(usize, f64)>
+    where
+        F: Fn(&Actor) -> bool,
+    {
+        let (x, y) = actors[i].get_pos();
+        self.visible()
+            .filter(|&index| filter(&actors[index]))
+            .map(|index| {
+                let (ox, oy) = actors[index].get_pos();
+                (index, vector::mag(ox - x, oy - y))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Everyone who has been seen recently but is out of sight now.
+    pub fn remembered(&self) -> impl Iterator<Item = &Memory> + '_ {
+        self.memory
+            .iter()
+            .filter(move |remembered| remembered.age > 0.0)
+    }
+
+    /// The most recent sighting of anyone who's out of sight now and passes
+    /// `filter`.
+    pub fn freshest_memory<F>(&self, actors: &[Actor], filter: F) -> Option<&Memory>
+    where
+        F: Fn(&Actor) -> bool,
+    {
+        self.remembered()
+            .filter(|remembered| {
+                remembered
+                    .target
+                    .index
+                    .is_some_and(|index| filter(&actors[index]))
+            })
+            .min_by(|a, b| a.age.total_cmp(&b.age))
+    }
+
+    pub fn description(&self) -> String {
+        format!(
+            "Sees: {}, remembers: {}",
+            self.visible.len(),
+            self.remembered().count()
+        )
+    }
+}
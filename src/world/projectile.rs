@@ -0,0 +1,83 @@
+use graphics::math::Matrix2d;
+use graphics::*;
+
+use crate::world::*;
+
+/// How fast a shot travels.
+const PROJECTILE_SPEED: f64 = 200.0;
+
+/// A shot in flight, homing in on its target. If the target dies before it
+/// lands, the shot carries on to wherever it was last seen and fizzles out.
+#[derive(Debug, Clone)]
+pub struct Projectile {
+    pub x: f64,
+    pub y: f64,
+    target: Target,
+    /// Where the target was last tick.
+    aim: (f64, f64),
+    damage: Damage,
+}
+
+impl Projectile {
+    pub fn new(x: f64, y: f64, target: Target, actors: &[Actor], damage: Damage) -> Self {
+        let aim = match target.index {
+            Some(index) => actors[index].get_pos(),
+            None => (x, y),
+        };
+        Self {
+            x,
+            y,
+            target,
+            aim,
+            damage,
+        }
+    }
+
+    /// Move every projectile along, and hurt whoever they land on. Returns
+    /// anyone killed.
+    pub fn update_all(
+        projectiles: &mut Vec<Projectile>,
+        actors: &mut [Actor],
+        dt: f64,
+    ) -> Vec<usize> {
+        let mut dead_actors = vec![];
+        projectiles.retain_mut(|projectile| {
+            let target = projectile
+                .target
+                .index
+                .filter(|&index| actors[index].death.is_none());
+            if let Some(index) = target {
+                projectile.aim = actors[index].get_pos();
+            }
+            let (x, y) = projectile.aim;
+            let step = PROJECTILE_SPEED * dt;
+            if !vector::distance_cmp(projectile.x, projectile.y, x, y, step) {
+                let (dx, dy) = vector::direction(projectile.x, projectile.y, x, y);
+                let (dx, dy) = vector::scale(dx, dy, step);
+                projectile.x += dx;
+                projectile.y += dy;
+                return true;
+            }
+            if let Some(index) = target {
+                if actors[index].take_damage(projectile.damage.clone()) {
+                    dead_actors.push(index);
+                }
+            }
+            false
+        });
+        dead_actors
+    }
+
+    pub fn fix_targets(&mut self, actors: &[Actor]) {
+        self.target.fix(actors);
+    }
+
+    pub fn render<G: Graphics>(&self, t: Matrix2d, g: &mut G) {
+        ellipse(
+            color::hex("ffee88"),
+            rectangle::centered_square(self.x, self.y, 1.5),
+            t,
+            g,
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! AI behaviours written in Rhai. Each script in the scripts directory defines
+//! a `next_task(me, nearby)` function, which gets called whenever one of its
+//! actors needs something to do:
+//!
+//! ```text
+//! fn next_task(me, nearby) {
+//!     if nearby.len() > 0 && nearby[0].relation == "hostile" {
+//!         return flee(nearby[0].target);
+//!     }
+//!     rest(2.0)
+//! }
+//! ```
+//!
+//! `me` describes the actor itself, and `nearby` the other actors it can see,
+//! closest first, each with a `relation` of `"ally"`, `"neutral"` or
+//! `"hostile"` towards `me`. Both are copies, so scripts can look but
+//! can't touch. Numbers passed to the task functions need to be written as
+//! floats (`2.0`, not `2`).
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::world::*;
+
+/// How often to look for scripts that have changed on disk, in seconds.
+const RELOAD_INTERVAL: f64 = 1.0;
+
+/// Scripts can loop forever, so cut them off after this many operations.
+const MAX_OPERATIONS: u64 = 50_000;
+
+/// An actor's use of a script, along with whatever went wrong the last time
+/// it ran.
+#[derive(Debug, Clone)]
+pub struct Scripted {
+    pub name: String,
+    pub error: Option<String>,
+}
+
+impl Scripted {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            error: None,
+        }
+    }
+
+    /// Run the script to find the actor's next task. Anything that goes
+    /// wrong gets kept for the description panel and the actor idles instead.
+    pub fn next_task(&mut self, i: usize, actors: &[Actor], ctx: &TaskContext) -> Task {
+        match ctx.scripts.run(&self.name, i, actors, ctx.relations) {
+            Ok(task) => {
+                self.error = None;
+                task
+            }
+            Err(e) => {
+                self.error = Some(e);
+                Task::idle()
+            }
+        }
+    }
+}
+
+struct Script {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    ast: Result<AST, String>,
+}
+
+/// Every script found in a directory, keyed by file name, and kept up to date
+/// with any changes made to them.
+pub struct Scripts {
+    dir: PathBuf,
+    engine: Engine,
+    scripts: HashMap<String, Script>,
+    since_check: f64,
+}
+
+impl Scripts {
+    pub fn load<P: AsRef<Path>>(dir: P) -> Self {
+        let mut scripts = Self {
+            dir: dir.as_ref().to_path_buf(),
+            engine: Scripts::engine(),
+            scripts: HashMap::new(),
+            since_check: 0.0,
+        };
+        scripts.reload();
+        scripts
+    }
+
+    /// No scripts at all, without looking on disk for any.
+    #[cfg(test)]
+    pub fn empty() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            engine: Scripts::engine(),
+            scripts: HashMap::new(),
+            since_check: 0.0,
+        }
+    }
+
+    /// Every so often, recompile any scripts that have been added or changed
+    /// since they were last loaded.
+    pub fn update(&mut self, dt: f64) {
+        self.since_check += dt;
+        if self.since_check >= RELOAD_INTERVAL {
+            self.since_check = 0.0;
+            self.reload();
+        }
+    }
+
+    /// An AI running the named script, if there's a script by that name.
+    pub fn ai(&self, name: &str) -> Option<ActorAi> {
+        if self.scripts.contains_key(name) {
+            Some(ActorAi::Scripted(Scripted::new(name)))
+        } else {
+            None
+        }
+    }
+
+    fn reload(&mut self) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", self.dir.display(), e);
+                return;
+            }
+        };
+        let mut found = HashSet::new();
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension() != Some("rhai".as_ref()) {
+                continue;
+            }
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            found.insert(name.clone());
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            if let Some(script) = self.scripts.get(&name) {
+                if script.modified == modified {
+                    continue;
+                }
+            }
+            let ast = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| self.engine.compile(&source).map_err(|e| e.to_string()));
+            if let Err(e) = &ast {
+                eprintln!("Couldn't load {}: {}", path.display(), e);
+            }
+            self.scripts.insert(
+                name,
+                Script {
+                    path,
+                    modified,
+                    ast,
+                },
+            );
+        }
+        // Anything whose file has gone shouldn't keep running from memory.
+        self.scripts.retain(|name, _| found.contains(name));
+    }
+
+    fn run(
+        &self,
+        name: &str,
+        i: usize,
+        actors: &[Actor],
+        relations: &Relations,
+    ) -> Result<Task, String> {
+        let script = match self.scripts.get(name) {
+            Some(script) => script,
+            None => return Err(format!("no script called {}", name)),
+        };
+        let ast = match &script.ast {
+            Ok(ast) => ast,
+            Err(e) => return Err(format!("{}: {}", script.path.display(), e)),
+        };
+        let mut me = view(i, &actors[i], 0.0);
+        me.insert("relation".into(), Relation::Ally.to_string().into());
+        let (x, y) = actors[i].get_pos();
+        let mut nearby: Vec<(f64, Map)> = actors[i]
+            .perception
+            .visible()
+            .map(|id| {
+                let (ox, oy) = actors[id].get_pos();
+                let distance = vector::mag(ox - x, oy - y);
+                let mut view = view(id, &actors[id], distance);
+                let relation = relations.get(actors[i].faction, actors[id].faction);
+                view.insert("relation".into(), relation.to_string().into());
+                (distance, view)
+            })
+            .collect();
+        nearby.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        let nearby: Array = nearby.into_iter().map(|(_, map)| map.into()).collect();
+        self.engine
+            .call_fn::<Task>(&mut Scope::new(), ast, "next_task", (me, nearby))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Set up an engine with everything scripts are allowed to call.
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+            .register_type_with_name::<Task>("Task")
+            .register_type_with_name::<Target>("Target")
+            .register_fn("idle", Task::idle)
+            .register_fn("move_to", Task::move_to)
+            .register_fn("chase", Task::move_to_actor)
+            .register_fn("flee", |target: Target| {
+                Task::RunFromActor(RunFromActor { target })
+            })
+            .register_fn("explode", Task::explode)
+            .register_fn("rest", Task::rest)
+            .register_fn("work", Task::work)
+            .register_fn("unload", Task::unload)
+            .register_fn("sequence", |tasks: Array| {
+                tasks
+                    .into_iter()
+                    .map(|task| task.try_cast::<Task>().ok_or("sequence only takes tasks"))
+                    .collect::<Result<Vec<Task>, _>>()
+                    .map(Task::sequence)
+                    .map_err(Box::<EvalAltResult>::from)
+            })
+            .register_fn("repeat", |times: i64, task: Task| {
+                u32::try_from(times)
+                    .ok()
+                    .filter(|&times| times > 0)
+                    .map(|times| Task::repeat(Some(times), task))
+                    .ok_or_else(|| {
+                        format!("repeat needs a count from 1 to {}, not {}", u32::MAX, times)
+                    })
+                    .map_err(Box::<EvalAltResult>::from)
+            })
+            .register_fn("timeout", |seconds: f64, task: Task| {
+                Task::timeout(seconds, task, None)
+            })
+            .register_fn("random", rand::random::<f64>);
+        engine
+    }
+}
+
+/// A copy of everything a script gets to know about an actor.
+fn view(i: usize, actor: &Actor, distance: f64) -> Map {
+    let mut map = Map::new();
+    let name = actor.name.clone().unwrap_or_default();
+    map.insert("name".into(), name.into());
+    map.insert("x".into(), actor.x.into());
+    map.insert("y".into(), actor.y.into());
+    map.insert("distance".into(), distance.into());
+    map.insert("load".into(), actor.load.into());
+    map.insert("can_move".into(), actor.can_move().into());
+    map.insert("body".into(), actor.body().to_string().into());
+    map.insert("faction".into(), actor.faction.to_string().into());
+    map.insert("ai".into(), actor.ai.to_string().into());
+    map.insert("dangerous".into(), actor.ai.is_dangerous().into());
+    map.insert("busy".into(), (!actor.tasks.is_empty()).into());
+    map.insert("target".into(), Dynamic::from(Target::new(i, actor.id)));
+    map
+}
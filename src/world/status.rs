@@ -0,0 +1,290 @@
+use graphics::math::Matrix2d;
+use graphics::types::Color;
+use graphics::*;
+
+use crate::world::*;
+
+/// The size of the icons drawn over an actor for each of its statuses.
+const ICON_SIZE: f64 = 3.0;
+
+/// Every kind of timed status an actor can be under.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatusKind {
+    /// Moves slower. `strength` is the fraction of speed lost.
+    Slow,
+    /// Moves faster. `strength` is the fraction of speed gained.
+    Haste,
+    /// Sees less far. `strength` is the fraction of sight lost.
+    Blind,
+    /// Can't do anything at all.
+    Stun,
+    /// Takes `strength` damage every second.
+    Burn,
+}
+
+/// What happens when a status is applied to an actor that already has one of
+/// the same kind.
+#[derive(Debug, Copy, Clone)]
+enum Stacking {
+    /// Keep a single copy, with the longer of the two durations and the
+    /// stronger of the two strengths.
+    Refresh,
+    /// Keep up to `max` copies side by side. Once full, a new copy replaces
+    /// whichever is closest to running out.
+    Stack { max: usize },
+}
+
+/// One timed status on an actor.
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub strength: f64,
+    pub remaining: f64,
+    /// Who caused it, for anything that does damage.
+    pub source: Option<DamageSource>,
+}
+
+/// Everything an actor is currently under, and the stats that follow from
+/// them.
+#[derive(Debug, Clone, Default)]
+pub struct Statuses {
+    active: Vec<Status>,
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 5] = [
+        StatusKind::Slow,
+        StatusKind::Haste,
+        StatusKind::Blind,
+        StatusKind::Stun,
+        StatusKind::Burn,
+    ];
+
+    fn stacking(self) -> Stacking {
+        match self {
+            StatusKind::Burn => Stacking::Stack { max: 3 },
+            _ => Stacking::Refresh,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            StatusKind::Slow => color::hex("4d80ff"),
+            StatusKind::Haste => color::hex("66ff66"),
+            StatusKind::Blind => color::hex("555555"),
+            StatusKind::Stun => color::hex("ffee33"),
+            StatusKind::Burn => color::hex("ff6600"),
+        }
+    }
+}
+
+impl Status {
+    pub fn new(
+        kind: StatusKind,
+        strength: f64,
+        duration: f64,
+        source: Option<DamageSource>,
+    ) -> Self {
+        Self {
+            kind,
+            strength,
+            remaining: duration,
+            source,
+        }
+    }
+}
+
+impl Statuses {
+    /// Put the actor under `status`, following the stacking rules for its
+    /// kind.
+    pub fn apply(&mut self, status: Status) {
+        let same = self.active.iter().filter(|s| s.kind == status.kind).count();
+        match status.kind.stacking() {
+            Stacking::Refresh => match self.active.iter_mut().find(|s| s.kind == status.kind) {
+                Some(existing) => {
+                    existing.remaining = existing.remaining.max(status.remaining);
+                    if status.strength > existing.strength {
+                        existing.strength = status.strength;
+                        existing.source = status.source;
+                    }
+                }
+                None => self.active.push(status),
+            },
+            Stacking::Stack { max } if same >= max => {
+                let oldest = self
+                    .active
+                    .iter_mut()
+                    .filter(|s| s.kind == status.kind)
+                    .min_by(|a, b| a.remaining.total_cmp(&b.remaining));
+                if let Some(oldest) = oldest {
+                    *oldest = status;
+                }
+            }
+            Stacking::Stack { .. } => self.active.push(status),
+        }
+    }
+
+    /// Count everything down, dropping whatever has run out. Returns any
+    /// damage done over this tick.
+    pub fn update(&mut self, dt: f64) -> Vec<Damage> {
+        let mut damage = vec![];
+        for status in self.active.iter_mut() {
+            let elapsed = dt.min(status.remaining);
+            status.remaining -= dt;
+            if status.kind == StatusKind::Burn {
+                damage.push(Damage::new(
+                    status.strength * elapsed,
+                    DamageKind::Burn,
+                    status.source.clone(),
+                ));
+            }
+        }
+        self.active.retain(|status| status.remaining > 0.0);
+        damage
+    }
+
+    pub fn speed_multiplier(&self) -> f64 {
+        self.active
+            .iter()
+            .map(|status| match status.kind {
+                StatusKind::Slow => (1.0 - status.strength).max(0.0),
+                StatusKind::Haste => 1.0 + status.strength,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn sight_multiplier(&self) -> f64 {
+        self.active
+            .iter()
+            .map(|status| match status.kind {
+                StatusKind::Blind => (1.0 - status.strength).max(0.0),
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn can_act(&self) -> bool {
+        !self
+            .active
+            .iter()
+            .any(|status| status.kind == StatusKind::Stun)
+    }
+
+    /// A row of small icons, one per status, centred just above (x, y).
+    pub fn render<G: Graphics>(&self, x: f64, y: f64, t: Matrix2d, g: &mut G) {
+        let width = self.active.len() as f64 * (ICON_SIZE + 1.0) - 1.0;
+        let mut icon_x = x - width / 2.0;
+        for status in self.active.iter() {
+            rectangle(
+                status.kind.color(),
+                [icon_x, y - ICON_SIZE, ICON_SIZE, ICON_SIZE],
+                t,
+                g,
+            );
+            icon_x += ICON_SIZE + 1.0;
+        }
+    }
+
+    pub fn description(&self) -> Option<String> {
+        if self.active.is_empty() {
+            return None;
+        }
+        let mut shown: Vec<String> = vec![];
+        for kind in StatusKind::ALL.iter() {
+            let of_kind: Vec<&Status> = self.active.iter().filter(|s| s.kind == *kind).collect();
+            let longest = of_kind.iter().map(|s| s.remaining).fold(0.0, f64::max);
+            match of_kind.len() {
+                0 => {}
+                1 => shown.push(format!("{} {:.1}s", kind, longest)),
+                stacks => shown.push(format!("{} x{} {:.1}s", kind, stacks, longest)),
+            }
+        }
+        Some(format!("Status: {}", shown.join(", ")))
+    }
+}
+
+impl fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                StatusKind::Slow => "slowed",
+                StatusKind::Haste => "hasted",
+                StatusKind::Blind => "blinded",
+                StatusKind::Stun => "stunned",
+                StatusKind::Burn => "burning",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(kind: StatusKind, strength: f64, duration: f64) -> Status {
+        Status::new(kind, strength, duration, None)
+    }
+
+    #[test]
+    fn refresh() {
+        let mut statuses = Statuses::default();
+        statuses.apply(status(StatusKind::Slow, 0.5, 2.0));
+        // Longer but weaker keeps the strength and takes the duration.
+        statuses.apply(status(StatusKind::Slow, 0.25, 5.0));
+        assert_eq!(statuses.active.len(), 1);
+        assert_eq!(statuses.active[0].remaining, 5.0);
+        assert_eq!(statuses.speed_multiplier(), 0.5);
+        // Stronger but shorter does the opposite.
+        statuses.apply(status(StatusKind::Slow, 0.75, 1.0));
+        assert_eq!(statuses.active.len(), 1);
+        assert_eq!(statuses.active[0].remaining, 5.0);
+        assert_eq!(statuses.speed_multiplier(), 0.25);
+    }
+
+    #[test]
+    fn stacking() {
+        let mut statuses = Statuses::default();
+        statuses.apply(status(StatusKind::Burn, 1.0, 3.0));
+        statuses.apply(status(StatusKind::Burn, 2.0, 1.0));
+        statuses.apply(status(StatusKind::Burn, 3.0, 2.0));
+        assert_eq!(statuses.active.len(), 3);
+        // Full, so the one closest to running out makes way.
+        statuses.apply(status(StatusKind::Burn, 4.0, 4.0));
+        assert_eq!(statuses.active.len(), 3);
+        let mut strengths: Vec<f64> = statuses.active.iter().map(|s| s.strength).collect();
+        strengths.sort_by(f64::total_cmp);
+        assert_eq!(strengths, vec![1.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn burns_stop_when_they_run_out() {
+        let mut statuses = Statuses::default();
+        statuses.apply(status(StatusKind::Burn, 10.0, 0.25));
+        let damage = statuses.update(1.0);
+        assert_eq!(damage.len(), 1);
+        // Only a quarter of a second was left to burn for.
+        assert_eq!(damage[0].amount, 2.5);
+        assert!(statuses.active.is_empty());
+        assert!(statuses.update(1.0).is_empty());
+    }
+
+    #[test]
+    fn multipliers() {
+        let mut statuses = Statuses::default();
+        assert_eq!(statuses.speed_multiplier(), 1.0);
+        statuses.apply(status(StatusKind::Slow, 0.5, 1.0));
+        statuses.apply(status(StatusKind::Haste, 1.0, 1.0));
+        assert_eq!(statuses.speed_multiplier(), 1.0);
+        // Slowed past a standstill is still just a standstill.
+        statuses.apply(status(StatusKind::Slow, 1.5, 1.0));
+        assert_eq!(statuses.speed_multiplier(), 0.0);
+        statuses.apply(status(StatusKind::Blind, 0.25, 1.0));
+        assert_eq!(statuses.sight_multiplier(), 0.75);
+        assert!(statuses.can_act());
+        statuses.apply(status(StatusKind::Stun, 1.0, 1.0));
+        assert!(!statuses.can_act());
+    }
+}
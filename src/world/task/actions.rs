@@ -0,0 +1,230 @@
+use super::*;
+
+/// Do nothing, and move on to whatever is next on the next tick.
+#[derive(Debug, Clone)]
+pub struct Idle;
+
+impl TaskKind for Idle {
+    fn execute(&mut self, _i: usize, _actors: &mut [Actor], _ctx: &TaskContext) -> TaskCompletion {
+        TaskCompletion::done()
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        "doing nothing".into()
+    }
+}
+
+/// Wait `delay` seconds, then create a new actor at the given offset.
+#[derive(Debug, Clone)]
+pub struct Spawn {
+    pub delay: f64,
+    pub x_offset: f64,
+    pub y_offset: f64,
+    pub ai: ActorAi,
+    pub body: ActorBody,
+}
+
+impl TaskKind for Spawn {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        self.delay -= ctx.dt;
+        if self.delay > 0.0 {
+            return TaskCompletion::new(NextAction::Continue);
+        }
+        let (x, y) = actors[i].get_pos();
+        let mut actor = Actor::new(
+            x + self.x_offset,
+            y + self.y_offset,
+            self.body,
+            self.ai.clone(),
+        );
+        // Whatever gets spawned is on the same side as whatever spawned it.
+        actor.faction = actors[i].faction;
+        TaskCompletion::done().spawn(actor)
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        format!("spawning a {} in {} seconds", self.ai, self.delay.floor())
+    }
+}
+
+/// Wait for `fuse` seconds, then blow up, taking this actor out along with it.
+/// Every hostile actor within `radius` takes up to `damage`, less the further
+/// they are from the centre.
+#[derive(Debug, Clone)]
+pub struct Explode {
+    pub radius: f64,
+    pub damage: f64,
+    pub fuse: f64,
+}
+
+impl TaskKind for Explode {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        if self.fuse > 0.0 {
+            self.fuse -= ctx.dt;
+            return TaskCompletion::new(NextAction::Continue);
+        }
+        let (x, y) = actors[i].get_pos();
+        TaskCompletion::done()
+            .damage(i, Damage::new(actors[i].hp, DamageKind::Blast, None))
+            .effect(AreaEffect::explosion(
+                x,
+                y,
+                self.radius,
+                self.damage,
+                DamageSource::new(i, actors),
+            ))
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        if self.fuse > 0.0 {
+            format!("exploding in {:.1} seconds", self.fuse)
+        } else {
+            "exploding".into()
+        }
+    }
+}
+
+/// Shoot at the target, then wait for the weapon to be ready again.
+#[derive(Debug, Clone)]
+pub struct Fire {
+    pub target: Target,
+    pub weapon: Weapon,
+    /// Seconds until the weapon is ready, once the shot has gone.
+    pub reloading: Option<f64>,
+}
+
+impl TaskKind for Fire {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        if let Some(reloading) = &mut self.reloading {
+            *reloading -= ctx.dt;
+            return TaskCompletion::new(if *reloading > 0.0 {
+                NextAction::Continue
+            } else {
+                NextAction::Done
+            });
+        }
+        let index = match self.target.index {
+            Some(index) if actors[index].death.is_none() => index,
+            _ => return TaskCompletion::done(),
+        };
+        let (x, y) = actors[i].get_pos();
+        let (tx, ty) = actors[index].get_pos();
+        actors[i].heading = (ty - y).atan2(tx - x);
+        self.reloading = Some(1.0 / self.weapon.fire_rate);
+        let damage = Damage::new(
+            self.weapon.damage,
+            DamageKind::Shot,
+            Some(DamageSource::new(i, actors)),
+        );
+        TaskCompletion::new(NextAction::Continue).projectile(Projectile::new(
+            x,
+            y,
+            self.target.clone(),
+            actors,
+            damage,
+        ))
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.reloading {
+            Some(reloading) => format!("reloading for {:.1} seconds", reloading.max(0.0)),
+            None => format!("shooting at {}", self.target.name(actors)),
+        }
+    }
+
+    fn target(&self) -> Option<&Target> {
+        Some(&self.target)
+    }
+
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        Some(&mut self.target)
+    }
+
+    fn destination(&self, _actors: &[Actor]) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+/// Stand still and get some energy back.
+#[derive(Debug, Clone)]
+pub struct Rest {
+    pub remaining: f64,
+}
+
+impl TaskKind for Rest {
+    fn execute(&mut self, _i: usize, _actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        self.remaining -= ctx.dt;
+        TaskCompletion::new(if self.remaining > 0.0 {
+            NextAction::Continue
+        } else {
+            NextAction::Done
+        })
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        format!("resting for {} seconds", self.remaining.ceil())
+    }
+}
+
+/// Gather things to carry for a while, stopping early once the actor's hands
+/// are full.
+#[derive(Debug, Clone)]
+pub struct Work {
+    pub remaining: f64,
+}
+
+impl Work {
+    /// How much gets gathered per second.
+    const RATE: f64 = 1.0;
+}
+
+impl TaskKind for Work {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        self.remaining -= ctx.dt;
+        let actor = &mut actors[i];
+        actor.load = (actor.load + Work::RATE * ctx.dt).min(MAX_LOAD);
+        TaskCompletion::new(if self.remaining > 0.0 && actor.load < MAX_LOAD {
+            NextAction::Continue
+        } else {
+            NextAction::Done
+        })
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        format!("working for {} seconds", self.remaining.ceil())
+    }
+}
+
+/// Drop off everything being carried at the target, as long as it's close
+/// enough.
+#[derive(Debug, Clone)]
+pub struct Unload {
+    pub target: Target,
+}
+
+impl TaskKind for Unload {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], _ctx: &TaskContext) -> TaskCompletion {
+        if let Some(index) = self.target.index {
+            let (x, y) = actors[index].get_pos();
+            if vector::distance_cmp(actors[i].x, actors[i].y, x, y, ACTOR_REF_SIZE * 2.0) {
+                actors[i].load = 0.0;
+            }
+        }
+        TaskCompletion::done()
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.target.index {
+            Some(_) => format!("unloading at {}", self.target.name(actors)),
+            None => "unloading".into(),
+        }
+    }
+
+    fn target(&self) -> Option<&Target> {
+        Some(&self.target)
+    }
+
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        Some(&mut self.target)
+    }
+}
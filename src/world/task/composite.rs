@@ -0,0 +1,253 @@
+use super::*;
+
+/// Run each task in turn, finishing once the last one does.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub tasks: Vec<Task>,
+    pub current: usize,
+}
+
+impl TaskKind for Sequence {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let task = match self.tasks.get_mut(self.current) {
+            Some(task) => task,
+            None => return TaskCompletion::done(),
+        };
+        let mut completion = task.execute(i, actors, ctx);
+        completion.next_action = match completion.next_action {
+            NextAction::Continue => NextAction::Continue,
+            NextAction::ChangeTo(next) => {
+                *task = next;
+                NextAction::Continue
+            }
+            NextAction::Done => {
+                self.current += 1;
+                if self.current < self.tasks.len() {
+                    NextAction::Continue
+                } else {
+                    NextAction::Done
+                }
+            }
+        };
+        completion
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.tasks.get(self.current) {
+            Some(task) => format!(
+                "{} (step {} of {})",
+                task.description(actors),
+                self.current + 1,
+                self.tasks.len()
+            ),
+            None => "finishing up".into(),
+        }
+    }
+
+    fn target(&self) -> Option<&Target> {
+        self.tasks.get(self.current).and_then(|task| task.target())
+    }
+
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        for task in self.tasks.iter_mut() {
+            task.fix_targets(actors);
+        }
+    }
+
+    fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.tasks
+            .get(self.current)
+            .and_then(|task| task.destination(actors))
+    }
+}
+
+/// Run a task over and over again, either a set number of times or forever.
+#[derive(Debug, Clone)]
+pub struct Repeat {
+    pub template: Box<Task>,
+    pub current: Box<Task>,
+    /// How many more runs after the current one, or `None` to never stop.
+    pub remaining: Option<u32>,
+}
+
+impl TaskKind for Repeat {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let mut completion = self.current.execute(i, actors, ctx);
+        completion.next_action = match completion.next_action {
+            NextAction::Continue => NextAction::Continue,
+            NextAction::ChangeTo(next) => {
+                *self.current = next;
+                NextAction::Continue
+            }
+            NextAction::Done => match self.remaining {
+                Some(0) => NextAction::Done,
+                _ => {
+                    self.remaining = self.remaining.map(|remaining| remaining - 1);
+                    self.current = self.template.clone();
+                    NextAction::Continue
+                }
+            },
+        };
+        completion
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.remaining {
+            Some(remaining) => format!(
+                "{} ({} more times)",
+                self.current.description(actors),
+                remaining
+            ),
+            None => format!("{} (repeating forever)", self.current.description(actors)),
+        }
+    }
+
+    fn target(&self) -> Option<&Target> {
+        self.current.target()
+    }
+
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        self.template.fix_targets(actors);
+        self.current.fix_targets(actors);
+    }
+
+    fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.current.destination(actors)
+    }
+}
+
+/// Give a task a limited amount of time to finish, then switch to a fallback
+/// (or just give up) if it hasn't.
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    pub remaining: f64,
+    pub task: Box<Task>,
+    pub on_timeout: Option<Box<Task>>,
+}
+
+impl TaskKind for Timeout {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        self.remaining -= ctx.dt;
+        if self.remaining <= 0.0 {
+            return match self.on_timeout.take() {
+                Some(fallback) => TaskCompletion::new(NextAction::ChangeTo(*fallback)),
+                None => TaskCompletion::done(),
+            };
+        }
+        let mut completion = self.task.execute(i, actors, ctx);
+        if let NextAction::ChangeTo(next) = completion.next_action {
+            *self.task = next;
+            completion.next_action = NextAction::Continue;
+        }
+        completion
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        format!(
+            "{} (giving up in {} seconds)",
+            self.task.description(actors),
+            self.remaining.ceil()
+        )
+    }
+
+    fn target(&self) -> Option<&Target> {
+        self.task.target()
+    }
+
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        self.task.fix_targets(actors);
+        if let Some(ref mut fallback) = self.on_timeout {
+            fallback.fix_targets(actors);
+        }
+    }
+
+    fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.task.destination(actors)
+    }
+}
+
+/// Run a task until it either finishes on its own or the condition comes
+/// true, whichever happens first.
+#[derive(Debug, Clone)]
+pub struct RaceUntil {
+    pub task: Box<Task>,
+    pub until: Condition,
+}
+
+impl TaskKind for RaceUntil {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        if self.until.check(i, actors, ctx) {
+            return TaskCompletion::done().targeted(self.task.target().cloned());
+        }
+        let mut completion = self.task.execute(i, actors, ctx);
+        if let NextAction::ChangeTo(next) = completion.next_action {
+            *self.task = next;
+            completion.next_action = NextAction::Continue;
+        }
+        completion
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        format!(
+            "{} until {}",
+            self.task.description(actors),
+            self.until.description(actors)
+        )
+    }
+
+    fn target(&self) -> Option<&Target> {
+        self.task.target()
+    }
+
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        self.task.fix_targets(actors);
+        self.until.fix_targets(actors);
+    }
+
+    fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.task.destination(actors)
+    }
+}
+
+/// Something about the world that a `RaceUntil` waits on.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// The target is within `distance` of the actor.
+    Near { target: Target, distance: f64 },
+    /// Some other actor has come within `distance` of this one.
+    Crowded { distance: f64 },
+}
+
+impl Condition {
+    pub fn check(&self, i: usize, actors: &[Actor], ctx: &TaskContext) -> bool {
+        let (x, y) = actors[i].get_pos();
+        match self {
+            Condition::Near { target, distance } => match target.index {
+                Some(index) => {
+                    let (tx, ty) = actors[index].get_pos();
+                    vector::distance_cmp(x, y, tx, ty, *distance)
+                }
+                None => false,
+            },
+            Condition::Crowded { distance } => ctx
+                .qt
+                .query(&Region::new_circle(x, y, *distance))
+                .iter()
+                .any(|actor_ref| actor_ref.id != i),
+        }
+    }
+
+    pub fn description(&self, actors: &[Actor]) -> String {
+        match self {
+            Condition::Near { target, .. } => format!("near {}", target.name(actors)),
+            Condition::Crowded { .. } => "someone gets close".into(),
+        }
+    }
+
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        match self {
+            Condition::Near { target, .. } => target.fix(actors),
+            Condition::Crowded { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::time::Instant;
+
+use crate::world::*;
+use map::{Map, WorldBounds};
+
+mod actions;
+mod composite;
+mod movement;
+
+pub use actions::*;
+pub use composite::*;
+pub use movement::*;
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub index: Option<usize>,
+    pub id: Instant,
+}
+
+impl Target {
+    pub fn new(index: usize, id: Instant) -> Self {
+        Self {
+            index: Some(index),
+            id,
+        }
+    }
+
+    /// Actors get removed from the array as they die, so the index may now be
+    /// too high. Look backwards from it for the actor with the right id and
+    /// point at that instead, or at nothing if it's gone.
+    pub fn fix(&mut self, actors: &[Actor]) {
+        if let Some(index) = self.index {
+            self.index = actors
+                .iter()
+                .enumerate()
+                .take(index + 1)
+                .rev()
+                .find(|(_, actor)| actor.id == self.id)
+                .map(|(i, _)| i);
+        }
+    }
+
+    pub fn name(&self, actors: &[Actor]) -> String {
+        match self.index.and_then(|index| actors[index].name.as_ref()) {
+            Some(name) => name.clone(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Everything about the world a task might need while executing, other than
+/// the actors themselves.
+pub struct TaskContext<'a> {
+    pub dt: f64,
+    pub qt: &'a QuadTree<ActorRef>,
+    pub bounds: &'a WorldBounds,
+    pub map: &'a Map,
+    /// How far through the day it is, from 0 at midnight up to 1.
+    pub time_of_day: f64,
+    pub scripts: &'a Scripts,
+    pub relations: &'a Relations,
+}
+
+/// The behaviour shared by every kind of task. Each kind keeps its parameters
+/// and all of its logic together in one type.
+pub trait TaskKind {
+    /// Run one tick of this task for the actor at index `i`. The task has been
+    /// taken off of the actor while it runs.
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion;
+
+    fn description(&self, actors: &[Actor]) -> String;
+
+    fn target(&self) -> Option<&Target> {
+        None
+    }
+
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        None
+    }
+
+    /// Point every target this task refers to back at the right actor after
+    /// some have been removed. Tasks holding other tasks need to pass this on.
+    fn fix_targets(&mut self, actors: &[Actor]) {
+        if let Some(target) = self.target_mut() {
+            target.fix(actors);
+        }
+    }
+
+    /// Where the actor will end up once this task is done, if anywhere in
+    /// particular.
+    fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.target()
+            .and_then(|target| target.index)
+            .map(|index| actors[index].get_pos())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Task {
+    Idle(Idle),
+    MoveTo(MoveTo),
+    MoveToActor(MoveToActor),
+    FollowPath(FollowPath),
+    RunFromActor(RunFromActor),
+    Spawn(Spawn),
+    Explode(Explode),
+    Fire(Fire),
+    Rest(Rest),
+    Work(Work),
+    Unload(Unload),
+    Sequence(Sequence),
+    Repeat(Repeat),
+    Timeout(Timeout),
+    RaceUntil(RaceUntil),
+}
+
+impl Task {
+    pub fn idle() -> Self {
+        Task::Idle(Idle)
+    }
+
+    pub fn move_to(x: f64, y: f64) -> Self {
+        Task::MoveTo(MoveTo { x, y })
+    }
+
+    pub fn move_to_actor(target: Target, max_distance: f64) -> Self {
+        Task::MoveToActor(MoveToActor {
+            target,
+            max_distance,
+        })
+    }
+
+    /// Walk through each of `waypoints` in turn.
+    pub fn follow_path(waypoints: Vec<(f64, f64)>) -> Self {
+        Task::FollowPath(FollowPath { waypoints })
+    }
+
+    pub fn run_from(index: usize, id: Instant) -> Self {
+        Task::RunFromActor(RunFromActor {
+            target: Target::new(index, id),
+        })
+    }
+
+    pub fn spawn(delay: f64, x_offset: f64, y_offset: f64, ai: ActorAi, body: ActorBody) -> Self {
+        Task::Spawn(Spawn {
+            delay,
+            x_offset,
+            y_offset,
+            ai,
+            body,
+        })
+    }
+
+    pub fn explode() -> Self {
+        Task::explode_after(0.0)
+    }
+
+    /// Blow up once `fuse` seconds have gone by.
+    pub fn explode_after(fuse: f64) -> Self {
+        Task::Explode(Explode {
+            radius: 25.0,
+            damage: 60.0,
+            fuse,
+        })
+    }
+
+    pub fn fire(target: Target, weapon: Weapon) -> Self {
+        Task::Fire(Fire {
+            target,
+            weapon,
+            reloading: None,
+        })
+    }
+
+    pub fn rest(seconds: f64) -> Self {
+        Task::Rest(Rest { remaining: seconds })
+    }
+
+    pub fn work(seconds: f64) -> Self {
+        Task::Work(Work { remaining: seconds })
+    }
+
+    pub fn unload(target: Target) -> Self {
+        Task::Unload(Unload { target })
+    }
+
+    pub fn sequence(tasks: Vec<Task>) -> Self {
+        Task::Sequence(Sequence { tasks, current: 0 })
+    }
+
+    /// Run `task` `times` times in total, or forever if `times` is `None`.
+    /// It always runs at least once, so `Some(0)` is the same as `Some(1)`.
+    pub fn repeat(times: Option<u32>, task: Task) -> Self {
+        Task::Repeat(Repeat {
+            template: Box::new(task.clone()),
+            current: Box::new(task),
+            remaining: times.map(|times| times.saturating_sub(1)),
+        })
+    }
+
+    pub fn timeout(seconds: f64, task: Task, on_timeout: Option<Task>) -> Self {
+        Task::Timeout(Timeout {
+            remaining: seconds,
+            task: Box::new(task),
+            on_timeout: on_timeout.map(Box::new),
+        })
+    }
+
+    pub fn race_until(until: Condition, task: Task) -> Self {
+        Task::RaceUntil(RaceUntil {
+            task: Box::new(task),
+            until,
+        })
+    }
+
+    fn kind(&self) -> &dyn TaskKind {
+        match self {
+            Task::Idle(task) => task,
+            Task::MoveTo(task) => task,
+            Task::MoveToActor(task) => task,
+            Task::FollowPath(task) => task,
+            Task::RunFromActor(task) => task,
+            Task::Spawn(task) => task,
+            Task::Explode(task) => task,
+            Task::Fire(task) => task,
+            Task::Rest(task) => task,
+            Task::Work(task) => task,
+            Task::Unload(task) => task,
+            Task::Sequence(task) => task,
+            Task::Repeat(task) => task,
+            Task::Timeout(task) => task,
+            Task::RaceUntil(task) => task,
+        }
+    }
+
+    fn kind_mut(&mut self) -> &mut dyn TaskKind {
+        match self {
+            Task::Idle(task) => task,
+            Task::MoveTo(task) => task,
+            Task::MoveToActor(task) => task,
+            Task::FollowPath(task) => task,
+            Task::RunFromActor(task) => task,
+            Task::Spawn(task) => task,
+            Task::Explode(task) => task,
+            Task::Fire(task) => task,
+            Task::Rest(task) => task,
+            Task::Work(task) => task,
+            Task::Unload(task) => task,
+            Task::Sequence(task) => task,
+            Task::Repeat(task) => task,
+            Task::Timeout(task) => task,
+            Task::RaceUntil(task) => task,
+        }
+    }
+
+    pub fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        self.kind_mut().execute(i, actors, ctx)
+    }
+
+    pub fn fix_targets(&mut self, actors: &[Actor]) {
+        self.kind_mut().fix_targets(actors);
+    }
+
+    pub fn description(&self, actors: &[Actor]) -> String {
+        self.kind().description(actors)
+    }
+
+    pub fn target(&self) -> Option<&Target> {
+        self.kind().target()
+    }
+
+    pub fn get_target_index(&self) -> Option<usize> {
+        self.target().and_then(|target| target.index)
+    }
+
+    pub fn destination(&self, actors: &[Actor]) -> Option<(f64, f64)> {
+        self.kind().destination(actors)
+    }
+}
+
+pub struct TaskCompletion {
+    pub next_action: NextAction,
+    pub prev_target: Option<Target>,
+    pub new_actor: Option<Actor>,
+    pub damage: Vec<(usize, Damage)>,
+    pub new_effect: Option<AreaEffect>,
+    pub new_projectile: Option<Projectile>,
+}
+
+impl TaskCompletion {
+    pub fn new(next_action: NextAction) -> Self {
+        TaskCompletion {
+            next_action,
+            new_actor: None,
+            damage: vec![],
+            prev_target: None,
+            new_effect: None,
+            new_projectile: None,
+        }
+    }
+
+    pub fn done() -> Self {
+        TaskCompletion::new(NextAction::Done)
+    }
+
+    pub fn spawn(mut self, actor: Actor) -> Self {
+        self.new_actor = Some(actor);
+        self
+    }
+
+    pub fn damage(mut self, i: usize, damage: Damage) -> Self {
+        self.damage.push((i, damage));
+        self
+    }
+
+    pub fn targeted(mut self, target: Option<Target>) -> Self {
+        self.prev_target = target;
+        self
+    }
+
+    pub fn effect(mut self, effect: AreaEffect) -> Self {
+        self.new_effect = Some(effect);
+        self
+    }
+
+    pub fn projectile(mut self, projectile: Projectile) -> Self {
+        self.new_projectile = Some(projectile);
+        self
+    }
+}
+
+pub enum NextAction {
+    /// Keep running the current task next tick.
+    Continue,
+    /// The current task is finished. The next one in the actor's queue runs,
+    /// or the AI picks one if the queue is empty.
+    Done,
+    /// Swap the current task out for a different one, leaving the rest of the
+    /// queue alone.
+    ChangeTo(Task),
+}
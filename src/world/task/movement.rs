@@ -0,0 +1,158 @@
+use super::*;
+
+/// Walk to a fixed spot, running away from anything hostile bumped into on the
+/// way.
+#[derive(Debug, Clone)]
+pub struct MoveTo {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl TaskKind for MoveTo {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let collided = ctx
+            .qt
+            .query(&actors[i].get_region())
+            .into_iter()
+            .find(|a| {
+                a.id != i
+                    && ctx
+                        .relations
+                        .is_hostile(actors[i].faction, actors[a.id].faction)
+            })
+            .map(|a| a.id);
+        TaskCompletion::new(match collided {
+            Some(other) => NextAction::ChangeTo(Task::run_from(other, actors[other].id)),
+            None => {
+                if actors[i].step_towards(self.x, self.y, ctx.dt, ctx.map) {
+                    NextAction::Done
+                } else {
+                    NextAction::Continue
+                }
+            }
+        })
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        format!("moving to ({}, {})", self.x.round(), self.y.round())
+    }
+
+    fn destination(&self, _actors: &[Actor]) -> Option<(f64, f64)> {
+        Some((self.x, self.y))
+    }
+}
+
+/// Walk through a list of spots in order, without stopping for anyone.
+#[derive(Debug, Clone)]
+pub struct FollowPath {
+    pub waypoints: Vec<(f64, f64)>,
+}
+
+impl TaskKind for FollowPath {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let (x, y) = match self.waypoints.first() {
+            Some(&waypoint) => waypoint,
+            None => return TaskCompletion::done(),
+        };
+        if actors[i].step_towards(x, y, ctx.dt, ctx.map) {
+            self.waypoints.remove(0);
+        }
+        TaskCompletion::new(if self.waypoints.is_empty() {
+            NextAction::Done
+        } else {
+            NextAction::Continue
+        })
+    }
+
+    fn description(&self, _actors: &[Actor]) -> String {
+        format!("following a path, {} steps to go", self.waypoints.len())
+    }
+
+    fn destination(&self, _actors: &[Actor]) -> Option<(f64, f64)> {
+        self.waypoints.last().copied()
+    }
+}
+
+/// Chase another actor until within `max_distance` of it.
+#[derive(Debug, Clone)]
+pub struct MoveToActor {
+    pub target: Target,
+    pub max_distance: f64,
+}
+
+impl TaskKind for MoveToActor {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let index = match self.target.index {
+            Some(index) => index,
+            None => return TaskCompletion::done(),
+        };
+        let (x, y) = actors[index].get_pos();
+        let arrived = actors[i].step_towards(x, y, ctx.dt, ctx.map);
+        TaskCompletion::new(
+            if arrived || vector::distance_cmp(actors[i].x, actors[i].y, x, y, self.max_distance) {
+                NextAction::Done
+            } else {
+                NextAction::Continue
+            },
+        )
+        .targeted(Some(self.target.clone()))
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.target.index {
+            Some(_) => format!("chasing {}", self.target.name(actors)),
+            None => "chasing".into(),
+        }
+    }
+
+    fn target(&self) -> Option<&Target> {
+        Some(&self.target)
+    }
+
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        Some(&mut self.target)
+    }
+}
+
+/// Run directly away from another actor until it's out of sight range.
+#[derive(Debug, Clone)]
+pub struct RunFromActor {
+    pub target: Target,
+}
+
+impl TaskKind for RunFromActor {
+    fn execute(&mut self, i: usize, actors: &mut [Actor], ctx: &TaskContext) -> TaskCompletion {
+        let index = match self.target.index {
+            Some(index) => index,
+            None => return TaskCompletion::done(),
+        };
+        let (x, y) = actors[index].get_pos();
+        let done = actors[i].step_from(x, y, ctx.dt, ctx.map);
+        // Once turned around the actor won't be able to see what it's running
+        // from any more, so keep going until it's out of range instead.
+        TaskCompletion::new(if !done && actors[i].within_sight(x, y) {
+            NextAction::Continue
+        } else {
+            NextAction::Done
+        })
+    }
+
+    fn description(&self, actors: &[Actor]) -> String {
+        match self.target.index {
+            Some(_) => format!("running from {}", self.target.name(actors)),
+            None => "running".into(),
+        }
+    }
+
+    fn destination(&self, _actors: &[Actor]) -> Option<(f64, f64)> {
+        None
+    }
+
+    fn target(&self) -> Option<&Target> {
+        Some(&self.target)
+    }
+
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        Some(&mut self.target)
+    }
+}
@@ -0,0 +1,102 @@
+use crate::world::*;
+
+/// What a tower shoots with.
+#[derive(Debug, Copy, Clone)]
+pub struct Weapon {
+    /// How far away a target can be.
+    pub range: f64,
+    /// Shots per second.
+    pub fire_rate: f64,
+    /// How much each shot takes off of whatever it hits.
+    pub damage: f64,
+}
+
+/// How a tower picks which of the enemies in range to shoot at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TargetPolicy {
+    /// Whoever is furthest along their way to the goal.
+    First,
+    Nearest,
+    /// Whoever has the most HP left.
+    Strongest,
+    /// Whoever has the least HP left.
+    Weakest,
+}
+
+impl TargetPolicy {
+    /// The policy after this one, for cycling through them all.
+    pub fn next(self) -> Self {
+        match self {
+            TargetPolicy::First => TargetPolicy::Nearest,
+            TargetPolicy::Nearest => TargetPolicy::Strongest,
+            TargetPolicy::Strongest => TargetPolicy::Weakest,
+            TargetPolicy::Weakest => TargetPolicy::First,
+        }
+    }
+
+    /// The hostile actor within `range` of actor `i` that this policy likes
+    /// best, if there are any.
+    pub fn pick(self, i: usize, range: f64, actors: &[Actor], ctx: &TaskContext) -> Option<usize> {
+        let (x, y) = actors[i].get_pos();
+        let faction = actors[i].faction;
+        let mut in_range: Vec<usize> = ctx
+            .qt
+            .query(&Region::new_circle(x, y, range))
+            .into_iter()
+            .map(|actor_ref| actor_ref.id)
+            .filter(|&id| id != i && actors[id].death.is_none())
+            .filter(|&id| ctx.relations.is_hostile(faction, actors[id].faction))
+            .collect();
+        // Ties go to whoever comes first, whatever order the tree found them.
+        in_range.sort_unstable();
+        let score = |id: usize| {
+            let other = &actors[id];
+            match self {
+                TargetPolicy::First => distance_left(other, ctx),
+                TargetPolicy::Nearest => vector::mag(other.x - x, other.y - y),
+                TargetPolicy::Strongest => -other.hp,
+                TargetPolicy::Weakest => other.hp,
+            }
+        };
+        in_range
+            .into_iter()
+            .map(|id| (id, score(id)))
+            .fold(None, |best: Option<(usize, f64)>, (id, score)| match best {
+                Some((_, best_score)) if best_score <= score => best,
+                _ => Some((id, score)),
+            })
+            .map(|(id, _)| id)
+    }
+}
+
+/// Roughly how far `actor` still has to go to reach the goal: the rest of
+/// the path it's following, or the straight line there if it isn't on one.
+fn distance_left(actor: &Actor, ctx: &TaskContext) -> f64 {
+    if let Some(Task::FollowPath(path)) = actor.tasks.front() {
+        let mut from = actor.get_pos();
+        let mut total = 0.0;
+        for &(x, y) in path.waypoints.iter() {
+            total += vector::mag(x - from.0, y - from.1);
+            from = (x, y);
+        }
+        return total;
+    }
+    match ctx.map.goal {
+        Some((cell_x, cell_y)) => {
+            let (x, y) = ctx.map.cell_center(cell_x, cell_y);
+            vector::mag(x - actor.x, y - actor.y)
+        }
+        None => f64::INFINITY,
+    }
+}
+
+impl fmt::Display for TargetPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetPolicy::First => write!(f, "first"),
+            TargetPolicy::Nearest => write!(f, "nearest"),
+            TargetPolicy::Strongest => write!(f, "strongest"),
+            TargetPolicy::Weakest => write!(f, "weakest"),
+        }
+    }
+}
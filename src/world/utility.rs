@@ -0,0 +1,396 @@
+use crate::world::*;
+
+/// How close something dangerous has to be before it's worth worrying about.
+const THREAT_RANGE: f64 = 80.0;
+
+/// How long a single bout of resting or working lasts.
+const REST_TIME: f64 = 4.0;
+const WORK_TIME: f64 = 3.0;
+
+/// An AI that keeps track of what the actor needs, and picks whichever of its
+/// options best meets those needs right now.
+#[derive(Debug, Clone)]
+pub struct Utility {
+    pub needs: Needs,
+    /// How each option scored the last time a choice was made, in the same
+    /// order as `CHOICES`.
+    scores: Vec<f64>,
+    chosen: Option<usize>,
+}
+
+/// How well looked after the actor is, from 0 (desperate) to 1 (satisfied).
+#[derive(Debug, Clone)]
+pub struct Needs {
+    pub energy: f64,
+    pub safety: f64,
+    pub work: f64,
+}
+
+/// Something about the actor or its surroundings, scaled to be between 0 and
+/// 1.
+#[derive(Debug, Copy, Clone)]
+enum Input {
+    Energy,
+    Safety,
+    Work,
+    /// How full the actor's hands are.
+    Load,
+    /// How far away the nearest threat is, relative to `THREAT_RANGE`.
+    ThreatDistance,
+    /// 0 at midnight, 1 at noon.
+    Daylight,
+}
+
+/// Turns an input into how much it matters to a particular choice.
+#[derive(Debug, Copy, Clone)]
+enum Curve {
+    Linear {
+        slope: f64,
+        offset: f64,
+    },
+    /// `x` raised to a power; matters more and more as the input goes up.
+    Rising(f64),
+    /// `1 - x` raised to a power; matters more and more as the input goes
+    /// down.
+    Falling(f64),
+    /// An S-shaped curve that flips from 0 to 1 around `midpoint`.
+    Logistic {
+        midpoint: f64,
+        steepness: f64,
+    },
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Act {
+    Rest,
+    Work,
+    Unload,
+    Flee,
+    Wander,
+}
+
+/// One thing the actor could choose to do, and what goes into deciding how
+/// good an idea it is. The considerations' scores are multiplied together, so
+/// any one of them can rule the choice out.
+struct Choice {
+    name: &'static str,
+    act: Act,
+    considerations: &'static [(Input, Curve)],
+}
+
+const CHOICES: [Choice; 5] = [
+    Choice {
+        name: "rest",
+        act: Act::Rest,
+        considerations: &[
+            (Input::Energy, Curve::Falling(2.0)),
+            (
+                Input::Daylight,
+                Curve::Linear {
+                    slope: -0.5,
+                    offset: 1.0,
+                },
+            ),
+        ],
+    },
+    Choice {
+        name: "work",
+        act: Act::Work,
+        considerations: &[
+            (Input::Work, Curve::Falling(1.0)),
+            (Input::Load, Curve::Falling(2.0)),
+            (
+                Input::Energy,
+                Curve::Logistic {
+                    midpoint: 0.3,
+                    steepness: 10.0,
+                },
+            ),
+            (
+                Input::Daylight,
+                Curve::Linear {
+                    slope: 0.6,
+                    offset: 0.4,
+                },
+            ),
+        ],
+    },
+    Choice {
+        name: "unload",
+        act: Act::Unload,
+        considerations: &[(
+            Input::Load,
+            Curve::Logistic {
+                midpoint: 0.6,
+                steepness: 12.0,
+            },
+        )],
+    },
+    Choice {
+        name: "flee",
+        act: Act::Flee,
+        considerations: &[
+            (Input::Safety, Curve::Falling(1.0)),
+            (Input::ThreatDistance, Curve::Falling(3.0)),
+        ],
+    },
+    Choice {
+        name: "wander",
+        act: Act::Wander,
+        considerations: &[
+            (Input::Energy, Curve::Rising(0.5)),
+            (Input::Work, Curve::Rising(4.0)),
+        ],
+    },
+];
+
+impl Utility {
+    pub fn new() -> Self {
+        Self {
+            needs: Needs {
+                energy: 1.0,
+                safety: 1.0,
+                work: 0.5,
+            },
+            scores: vec![],
+            chosen: None,
+        }
+    }
+
+    /// Needs drift over time depending on what the actor is up to, and how
+    /// close the nearest threat is.
+    pub fn update(&mut self, dt: f64, task: Option<&Task>, threat: Option<f64>) {
+        let (energy, work) = match task {
+            Some(Task::Rest(_)) => (0.15, -0.02),
+            Some(Task::Work(_)) => (-0.03, 0.2),
+            _ => (-0.01, -0.02),
+        };
+        let safety = match threat {
+            Some(distance) => -0.5 * (1.0 - distance / THREAT_RANGE),
+            None => 0.1,
+        };
+        let needs = &mut self.needs;
+        needs.energy = (needs.energy + energy * dt).clamp(0.0, 1.0);
+        needs.work = (needs.work + work * dt).clamp(0.0, 1.0);
+        needs.safety = (needs.safety + safety * dt).clamp(0.0, 1.0);
+    }
+
+    /// Score every choice and turn the best one into a task.
+    pub fn next_task(&mut self, i: usize, actors: &[Actor], ctx: &TaskContext) -> Task {
+        let threat = nearest_threat(i, actors, ctx.relations);
+        let depot = nearest_depot(i, actors);
+        let actor = &actors[i];
+        self.scores = CHOICES
+            .iter()
+            .map(|choice| match (choice.act, depot) {
+                // Nowhere to drop things off, so there's no point trying.
+                (Act::Unload, None) => 0.0,
+                _ => choice.score(|input| match input {
+                    Input::Energy => self.needs.energy,
+                    Input::Safety => self.needs.safety,
+                    Input::Work => self.needs.work,
+                    Input::Load => actor.load / MAX_LOAD,
+                    Input::ThreatDistance => match threat {
+                        Some((_, distance)) => distance / THREAT_RANGE,
+                        None => 1.0,
+                    },
+                    Input::Daylight => {
+                        (1.0 - (ctx.time_of_day * std::f64::consts::PI * 2.0).cos()) / 2.0
+                    }
+                }),
+            })
+            .collect();
+        let best = best_choice(&self.scores);
+        self.chosen = best;
+        let act = match best {
+            Some(index) => CHOICES[index].act,
+            None => return Task::idle(),
+        };
+        let (x, y) = actor.get_pos();
+        match (act, threat, depot) {
+            (Act::Rest, _, _) => Task::rest(REST_TIME),
+            (Act::Work, _, _) => Task::work(WORK_TIME),
+            (Act::Unload, _, Some(depot)) => {
+                let target = Target::new(depot, actors[depot].id);
+                Task::sequence(vec![
+                    Task::move_to_actor(target.clone(), ACTOR_REF_SIZE),
+                    Task::unload(target),
+                ])
+            }
+            (Act::Flee, Some((threat, _)), _) => Task::run_from(threat, actors[threat].id),
+            _ => {
+                let angle = rand::random::<f64>() * std::f64::consts::PI * 2.0;
+                let (dx, dy) = vector::scale(angle.cos(), angle.sin(), 30.0);
+                Task::move_to(x + dx, y + dy)
+            }
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let mut desc = format!(
+            "Needs: energy {:.2}, safety {:.2}, work {:.2}",
+            self.needs.energy, self.needs.safety, self.needs.work
+        );
+        if !self.scores.is_empty() {
+            let scores: Vec<String> = CHOICES
+                .iter()
+                .zip(self.scores.iter())
+                .enumerate()
+                .map(|(index, (choice, score))| {
+                    let marker = if Some(index) == self.chosen { "*" } else { "" };
+                    format!("{}{} {:.2}", marker, choice.name, score)
+                })
+                .collect();
+            desc += format!("\nScores: {}", scores.join(", ")).as_str();
+        }
+        desc
+    }
+}
+
+impl Choice {
+    /// How good an idea this is, given the value of each input.
+    fn score(&self, input: impl Fn(Input) -> f64) -> f64 {
+        self.considerations
+            .iter()
+            .map(|&(kind, curve)| curve.score(input(kind).clamp(0.0, 1.0)))
+            .product()
+    }
+}
+
+/// Which of the scores is the highest. Anything that isn't a number never
+/// wins.
+fn best_choice(scores: &[f64]) -> Option<usize> {
+    scores
+        .iter()
+        .enumerate()
+        .filter(|(_, score)| !score.is_nan())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+impl Curve {
+    fn score(&self, x: f64) -> f64 {
+        let score = match *self {
+            Curve::Linear { slope, offset } => slope * x + offset,
+            Curve::Rising(power) => x.powf(power),
+            Curve::Falling(power) => (1.0 - x).powf(power),
+            Curve::Logistic {
+                midpoint,
+                steepness,
+            } => 1.0 / (1.0 + (-steepness * (x - midpoint)).exp()),
+        };
+        score.clamp(0.0, 1.0)
+    }
+}
+
+/// The closest hostile actor in sight that might blow up, and how far away it
+/// is.
+fn nearest_threat(i: usize, actors: &[Actor], relations: &Relations) -> Option<(usize, f64)> {
+    let faction = actors[i].faction;
+    actors[i]
+        .perception
+        .nearest(i, actors, |other| {
+            other.ai.is_dangerous() && relations.is_hostile(faction, other.faction)
+        })
+        .filter(|&(_, distance)| distance <= THREAT_RANGE)
+}
+
+/// The closest barracks of actor `i`'s own faction to drop things off at.
+fn nearest_depot(i: usize, actors: &[Actor]) -> Option<usize> {
+    let (x, y) = actors[i].get_pos();
+    let faction = actors[i].faction;
+    actors
+        .iter()
+        .enumerate()
+        .filter(|(_, actor)| {
+            actor.faction == faction
+                && matches!(actor.body(), ActorBody::Building(Structure::Barracks, _))
+        })
+        .map(|(index, actor)| {
+            let (dx, dy) = actor.get_pos();
+            (index, vector::mag(dx - x, dy - y))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+/// How far away the nearest threat to actor `i` is, if there's one in range.
+pub fn threat_distance(i: usize, actors: &[Actor], relations: &Relations) -> Option<f64> {
+    nearest_threat(i, actors, relations).map(|(_, distance)| distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves() {
+        let linear = Curve::Linear {
+            slope: -0.5,
+            offset: 1.0,
+        };
+        assert_eq!(linear.score(0.0), 1.0);
+        assert_eq!(linear.score(1.0), 0.5);
+        assert_eq!(Curve::Rising(2.0).score(0.5), 0.25);
+        assert_eq!(Curve::Falling(2.0).score(0.5), 0.25);
+        assert_eq!(Curve::Falling(1.0).score(1.0), 0.0);
+        let logistic = Curve::Logistic {
+            midpoint: 0.3,
+            steepness: 10.0,
+        };
+        assert_eq!(logistic.score(0.3), 0.5);
+        assert!(logistic.score(0.0) < 0.1);
+        assert!(logistic.score(1.0) > 0.9);
+        // Scores never leave 0 to 1, however steep the curve.
+        let steep = Curve::Linear {
+            slope: 5.0,
+            offset: -1.0,
+        };
+        assert_eq!(steep.score(0.0), 0.0);
+        assert_eq!(steep.score(1.0), 1.0);
+    }
+
+    #[test]
+    fn choice_scores() {
+        let rest = &CHOICES[0];
+        let daylight = |energy: f64| {
+            move |input| match input {
+                Input::Energy => energy,
+                Input::Daylight => 1.0,
+                _ => 0.0,
+            }
+        };
+        // Falling(2) on energy times half-strength daylight.
+        assert_eq!(rest.score(daylight(0.0)), 0.5);
+        assert_eq!(rest.score(daylight(1.0)), 0.0);
+        // Inputs are clamped before they go through the curves.
+        assert_eq!(rest.score(daylight(-3.0)), 0.5);
+    }
+
+    #[test]
+    fn best_choice_ignores_nan() {
+        assert_eq!(best_choice(&[0.2, 0.7, 0.4]), Some(1));
+        assert_eq!(best_choice(&[0.2, f64::NAN, 0.4]), Some(2));
+        assert_eq!(best_choice(&[]), None);
+    }
+
+    #[test]
+    fn depots_belong_to_the_same_faction() {
+        let barracks = |x, faction| {
+            let body = ActorBody::Building(Structure::Barracks, 0);
+            let mut actor = Actor::new(x, 0.0, body, ActorAi::Inert);
+            actor.faction = faction;
+            actor
+        };
+        let mut worker = Actor::new(0.0, 0.0, ActorBody::Worker, ActorAi::Wanderer);
+        worker.faction = Faction::Player;
+        let mut actors = vec![
+            worker,
+            barracks(10.0, Faction::Raiders),
+            barracks(50.0, Faction::Player),
+        ];
+        assert_eq!(nearest_depot(0, &actors), Some(2));
+        actors.truncate(2);
+        assert_eq!(nearest_depot(0, &actors), None);
+    }
+}
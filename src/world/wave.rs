@@ -0,0 +1,343 @@
+//! Waves are listed in order, each one followed by an indented line for
+//! every group of enemies in it:
+//!
+//! ```text
+//! # Anything after a '#' is ignored.
+//! wave 20
+//!     6 grunt every 1.5 from 0
+//!     2 brute every 4 from 1
+//! ```
+//!
+//! The number after `wave` is how many seconds to wait before sending the
+//! next one. Each group gives how many enemies to send, what kind, how many
+//! seconds apart, and which of the map's spawn points they come in at,
+//! counting from 0. Every group in a wave starts at once.
+
+use std::fs;
+use std::path::Path;
+
+use crate::world::behaviour::ParseError;
+use crate::world::map::Map;
+use crate::world::*;
+
+/// How long the player gets to prepare before the first wave.
+const FIRST_WAVE_DELAY: f64 = 15.0;
+
+/// The kinds of enemy a wave can be made up of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Enemy {
+    Grunt,
+    /// Quick, but doesn't take much to bring down.
+    Runner,
+    /// Slow, but takes a beating.
+    Brute,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    enemy: Enemy,
+    count: u32,
+    interval: f64,
+    spawn_point: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Wave {
+    groups: Vec<Group>,
+    /// Seconds between this wave starting and the next one.
+    delay: f64,
+}
+
+/// A group that's partway through coming in.
+struct Sending {
+    group: Group,
+    remaining: u32,
+    until_next: f64,
+}
+
+/// Sends each wave in turn, once the one before has had its time.
+pub struct Waves {
+    waves: Vec<Wave>,
+    /// How many waves have been sent so far.
+    sent: usize,
+    /// Seconds until the next wave.
+    countdown: f64,
+    sending: Vec<Sending>,
+}
+
+impl Enemy {
+    fn from_name(name: &str) -> Option<Enemy> {
+        match name {
+            "grunt" => Some(Enemy::Grunt),
+            "runner" => Some(Enemy::Runner),
+            "brute" => Some(Enemy::Brute),
+            _ => None,
+        }
+    }
+
+    /// A new invader of this kind at (x, y).
+    fn spawn(self, x: f64, y: f64) -> Actor {
+        let mut actor = Actor::new(x, y, ActorBody::Worker, ActorAi::Invader);
+        actor.faction = Faction::Raiders;
+        let (speed, hp, bounty) = match self {
+            Enemy::Grunt => (1.0, 1.0, 5),
+            Enemy::Runner => (1.75, 0.5, 4),
+            Enemy::Brute => (0.6, 3.0, 15),
+        };
+        actor.speed = actor.speed.map(|base| base * speed);
+        actor.max_hp *= hp;
+        actor.hp = actor.max_hp;
+        actor.bounty = bounty;
+        actor
+    }
+}
+
+impl Waves {
+    /// Load the waves from a file, to be sent in on `map`. Falls back to the
+    /// defaults if the file can't be read, or asks for spawn points the map
+    /// doesn't have.
+    pub fn load<P: AsRef<Path>>(path: P, map: &Map) -> Self {
+        let source = match fs::read_to_string(path.as_ref()) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", path.as_ref().display(), e);
+                return Waves::fallback(map);
+            }
+        };
+        match Waves::parse(&source, map.spawn_points.len()) {
+            Ok(waves) => Waves::new(waves),
+            Err(e) => {
+                eprintln!("{}: {}", path.as_ref().display(), e);
+                Waves::fallback(map)
+            }
+        }
+    }
+
+    /// A handful of waves of grunts that get a little bigger and quicker
+    /// each time, coming in at each of the map's spawn points in turn.
+    fn fallback(map: &Map) -> Self {
+        let spawn_points = map.spawn_points.len().max(1);
+        Waves::new(
+            (0..5)
+                .map(|n| Wave {
+                    groups: vec![Group {
+                        enemy: Enemy::Grunt,
+                        count: 4 + n as u32 * 2,
+                        interval: 1.5 - n as f64 * 0.2,
+                        spawn_point: n % spawn_points,
+                    }],
+                    delay: 20.0,
+                })
+                .collect(),
+        )
+    }
+
+    fn new(waves: Vec<Wave>) -> Self {
+        Self {
+            waves,
+            sent: 0,
+            countdown: FIRST_WAVE_DELAY,
+            sending: vec![],
+        }
+    }
+
+    /// Read the waves out of `source`, for a map with `spawn_points` spawn
+    /// points.
+    fn parse(source: &str, spawn_points: usize) -> Result<Vec<Wave>, ParseError> {
+        let mut waves: Vec<Wave> = vec![];
+        for (number, line) in source.lines().enumerate() {
+            let error = |message: &str| ParseError {
+                line: number + 1,
+                message: message.into(),
+            };
+            let line = line.split('#').next().unwrap_or("");
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number_at =
+                |index: usize| words.get(index).and_then(|word| word.parse::<f64>().ok());
+            match words[..] {
+                [] => {}
+                ["wave", _] => match number_at(1) {
+                    Some(delay) if delay >= 0.0 => waves.push(Wave {
+                        groups: vec![],
+                        delay,
+                    }),
+                    _ => return Err(error("expected a number of seconds after \"wave\"")),
+                },
+                [count, enemy, "every", _, "from", spawn_point] => {
+                    let wave = match waves.last_mut() {
+                        Some(wave) if line.starts_with(char::is_whitespace) => wave,
+                        _ => return Err(error("groups need to be indented under a wave")),
+                    };
+                    let count = count
+                        .parse::<u32>()
+                        .map_err(|_| error(&format!("\"{}\" isn't a count", count)))?;
+                    let enemy = Enemy::from_name(enemy)
+                        .ok_or_else(|| error(&format!("no kind of enemy called \"{}\"", enemy)))?;
+                    let interval = match number_at(3) {
+                        Some(interval) if interval > 0.0 => interval,
+                        _ => return Err(error("expected a number of seconds after \"every\"")),
+                    };
+                    let spawn_point = spawn_point
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&index| index < spawn_points)
+                        .ok_or_else(|| {
+                            error(&format!(
+                                "\"{}\" isn't a spawn point; the map has {}",
+                                spawn_point, spawn_points
+                            ))
+                        })?;
+                    wave.groups.push(Group {
+                        enemy,
+                        count,
+                        interval,
+                        spawn_point,
+                    });
+                }
+                _ => {
+                    return Err(error(
+                        "expected \"wave <seconds>\" or \"<count> <enemy> every <seconds> from <spawn point>\"",
+                    ))
+                }
+            }
+        }
+        if waves.is_empty() {
+            return Err(ParseError {
+                line: 0,
+                message: "there aren't any waves".into(),
+            });
+        }
+        Ok(waves)
+    }
+
+    /// Count down to the next wave, and send in whoever is due from the
+    /// waves under way.
+    pub fn update(&mut self, dt: f64, map: &Map) -> Vec<Actor> {
+        if self.sent < self.waves.len() {
+            self.countdown -= dt;
+            if self.countdown <= 0.0 {
+                self.send_next();
+            }
+        }
+        let mut invaders = vec![];
+        for sending in self.sending.iter_mut() {
+            sending.until_next -= dt;
+            while sending.remaining > 0 && sending.until_next <= 0.0 {
+                let point = map.spawn_points.get(sending.group.spawn_point);
+                if let Some(&(cell_x, cell_y)) = point {
+                    let (x, y) = map.cell_center(cell_x, cell_y);
+                    invaders.push(sending.group.enemy.spawn(x, y));
+                }
+                sending.remaining -= 1;
+                sending.until_next += sending.group.interval;
+            }
+        }
+        self.sending.retain(|sending| sending.remaining > 0);
+        invaders
+    }
+
+    /// Send the next wave straight away rather than waiting for it.
+    pub fn call_early(&mut self) {
+        if self.sent < self.waves.len() {
+            self.send_next();
+        }
+    }
+
+    fn send_next(&mut self) {
+        let wave = &self.waves[self.sent];
+        for group in wave.groups.iter() {
+            self.sending.push(Sending {
+                group: group.clone(),
+                remaining: group.count,
+                until_next: 0.0,
+            });
+        }
+        self.countdown = wave.delay;
+        self.sent += 1;
+    }
+
+    /// How long until the next wave, if there are any left to come.
+    pub fn next_wave_in(&self) -> Option<f64> {
+        if self.sent < self.waves.len() {
+            Some(self.countdown.max(0.0))
+        } else {
+            None
+        }
+    }
+
+    /// How many waves have been sent so far.
+    pub fn sent(&self) -> usize {
+        self.sent
+    }
+
+    /// Whether every wave has been sent in full.
+    pub fn finished(&self) -> bool {
+        self.sent == self.waves.len() && self.sending.is_empty()
+    }
+
+    pub fn description(&self) -> String {
+        let mut desc = format!("Wave: {} of {}", self.sent, self.waves.len());
+        if let Some(seconds) = self.next_wave_in() {
+            desc += format!("\nNext wave in {:.0} s (N to call it now)", seconds.ceil()).as_str();
+        }
+        desc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_waves() {
+        let source = "
+            # Warm up.
+            wave 20
+                6 grunt every 1.5 from 0
+                2 brute every 4 from 1
+            wave 30
+                10 runner every 0.5 from 2
+        ";
+        let waves = Waves::parse(source, 3).unwrap();
+        assert_eq!(waves.len(), 2);
+        assert_eq!(waves[0].groups.len(), 2);
+        assert_eq!(waves[0].delay, 20.0);
+        assert_eq!(waves[0].groups[1].enemy, Enemy::Brute);
+        assert_eq!(waves[1].groups[0].count, 10);
+        assert_eq!(waves[1].groups[0].spawn_point, 2);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |source: &str| Waves::parse(source, 3).unwrap_err().line;
+        assert_eq!(error(""), 0);
+        assert_eq!(error("    6 grunt every 1 from 0"), 1);
+        assert_eq!(error("wave 10\n6 grunt every 1 from 0"), 2);
+        assert_eq!(error("wave 10\n    6 ogre every 1 from 0"), 2);
+        assert_eq!(error("wave 10\n    6 grunt every 0 from 0"), 2);
+        assert_eq!(error("wave soon"), 1);
+        assert_eq!(error("wave 10\n    6 grunt every 1 from 3"), 2);
+    }
+
+    #[test]
+    fn shipped_waves_parse() {
+        let source = fs::read_to_string("assets/waves.txt").unwrap();
+        // Maps with roads always have three ways in.
+        if let Err(e) = Waves::parse(&source, 3) {
+            panic!("assets/waves.txt: {}", e);
+        }
+    }
+
+    #[test]
+    fn fallback_spawns_on_the_map() {
+        let mut map = Map::new(10, 10);
+        map.spawn_points = vec![(0, 2), (9, 7)];
+        let waves = Waves::fallback(&map);
+        assert_eq!(waves.waves.len(), 5);
+        for wave in waves.waves.iter() {
+            for group in wave.groups.iter() {
+                assert!(group.spawn_point < map.spawn_points.len());
+            }
+        }
+    }
+}